@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
+use solana_sha256_hasher::hash;
 
 declare_id!("8FBDDMZbqinW6UdrBdCS6QeNgW1TLQCkq43MdQX8zqmM");
 
@@ -17,19 +19,234 @@ pub mod state {
         pub status: TaskStatus,
         pub accepted_bid: Option<Pubkey>,
         pub escrow_account: Option<Pubkey>,
+        pub requirements: BidRequirements,
+        pub invitees: Vec<Pubkey>,
+        pub category: TaskCategory,
+        /// Page of the `CategoryIndex` listing this task while it is open.
+        pub category_page: u32,
+        /// Bumped whenever the budget or milestones change, so bids made
+        /// against an older scope can be recognised as stale.
+        pub scope_version: u32,
+        /// Proposed new owner, who must accept before ownership moves.
+        pub pending_owner: Option<Pubkey>,
+        /// Keys that approve milestone payouts. Empty means the owner alone.
+        pub approvers: Vec<Pubkey>,
+        /// Approvals needed before a payout executes.
+        pub approval_threshold: u8,
         pub created_at: i64,
         pub updated_at: i64,
+        /// Off-chain description for compact tasks, which leave `description`
+        /// empty. `None` for tasks that carry their description inline.
+        pub metadata: Option<TaskMetadata>,
     }
 
     impl Task {
         pub const MAX_TITLE_LEN: usize = 100;
         pub const MAX_DESC_LEN: usize = 5000;
         pub const MAX_MILESTONES: usize = 10;
+        pub const MAX_INVITEES: usize = 10;
+        pub const MAX_APPROVERS: usize = 5;
         
-        pub fn space(milestone_count: usize) -> usize {
-            8 + 32 + 4 + Self::MAX_TITLE_LEN + 4 + Self::MAX_DESC_LEN + 8 +
-            4 + (milestone_count * Milestone::SIZE) + 8 + 1 + 1 + 32 + 1 + 32 + 8 + 8
+        /// Account size for the given content. Text and milestones are sized
+        /// to fit; everything else reserves its maximum.
+        pub fn space_with(
+            title: &str,
+            description: &str,
+            milestones: &[Milestone],
+            metadata: Option<&TaskMetadata>,
+        ) -> usize {
+            let milestones: usize = milestones.iter().map(Milestone::space).sum();
+            let metadata = metadata.map_or(0, TaskMetadata::space);
+            8 + 32 + 4 + title.len() + 4 + description.len() + 8 +
+            4 + milestones + 8 + 1 + 1 + 32 + 1 + 32 +
+            BidRequirements::SIZE + 4 + (Self::MAX_INVITEES * 32) + 1 + 4 + 4 + 33 +
+            4 + (Self::MAX_APPROVERS * 32) + 1 + 8 + 8 +
+            1 + metadata
         }
+        
+        /// Size of the account once `update_task` applies the given changes.
+        /// Invalid milestone operations are sized as no-ops; the handler
+        /// rejects them.
+        pub fn updated_space(
+            &self,
+            description: Option<&String>,
+            metadata: Option<&TaskMetadata>,
+            milestone_ops: &[MilestoneOp],
+        ) -> usize {
+            let milestones = Self::apply_milestone_ops(&self.milestones, milestone_ops)
+                .unwrap_or_else(|_| self.milestones.clone());
+            Self::space_with(
+                &self.title,
+                description.unwrap_or(&self.description),
+                &milestones,
+                metadata.or(self.metadata.as_ref()),
+            )
+        }
+        
+        pub fn apply_milestone_ops(
+            milestones: &[Milestone],
+            ops: &[MilestoneOp],
+        ) -> Result<Vec<Milestone>> {
+            let mut milestones = milestones.to_vec();
+            for op in ops {
+                match op {
+                    MilestoneOp::Add { description, amount } => milestones.push(Milestone {
+                        description: description.clone(),
+                        amount: *amount,
+                        completed: false,
+                        paid: false,
+                        approvals: 0,
+                    }),
+                    MilestoneOp::Edit { index, description, amount } => {
+                        let milestone = milestones
+                            .get_mut(*index as usize)
+                            .ok_or(AgoraError::InvalidMilestoneIndex)?;
+                        if let Some(description) = description {
+                            milestone.description = description.clone();
+                        }
+                        if let Some(amount) = amount {
+                            milestone.amount = *amount;
+                        }
+                    }
+                    MilestoneOp::Remove { index } => {
+                        require!(
+                            (*index as usize) < milestones.len(),
+                            AgoraError::InvalidMilestoneIndex
+                        );
+                        milestones.remove(*index as usize);
+                    }
+                }
+            }
+            Ok(milestones)
+        }
+        
+        /// Checks a milestone schedule is well-formed and pays out exactly `budget`.
+        pub fn validate_schedule(milestones: &[Milestone], budget: u64) -> Result<()> {
+            require!(!milestones.is_empty(), AgoraError::NoMilestones);
+            require!(milestones.len() <= Self::MAX_MILESTONES, AgoraError::TooManyMilestones);
+            require!(
+                milestones.iter().all(|m| m.description.len() <= Milestone::MAX_DESC_LEN),
+                AgoraError::MilestoneDescriptionTooLong
+            );
+            
+            let total = milestones
+                .iter()
+                .try_fold(0u64, |sum, m| sum.checked_add(m.amount))
+                .ok_or(AgoraError::MathOverflow)?;
+            require!(total == budget, AgoraError::MilestoneAmountMismatch);
+            Ok(())
+        }
+        
+        pub fn is_compact(&self) -> bool {
+            self.metadata.is_some()
+        }
+        
+        /// Bit position of `key` in a milestone's `approvals`, if it may approve.
+        pub fn approver_index(&self, key: &Pubkey) -> Option<usize> {
+            if self.approvers.is_empty() {
+                (*key == self.owner).then_some(0)
+            } else {
+                self.approvers.iter().position(|k| k == key)
+            }
+        }
+        
+        /// Replaces the approver set. Pending approvals are indexed by the old
+        /// set, so unpaid milestones start over.
+        pub fn set_approvers(&mut self, approvers: Vec<Pubkey>, threshold: u8) {
+            self.approval_threshold = if approvers.is_empty() { 0 } else { threshold };
+            self.approvers = approvers;
+            for milestone in self.milestones.iter_mut().filter(|m| !m.paid) {
+                milestone.approvals = 0;
+            }
+        }
+        
+        /// Once escrow is funded, a designated approver set can no longer be
+        /// changed by the owner alone, or they could bypass the threshold.
+        pub fn approvers_locked(&self) -> bool {
+            self.escrow_account.is_some() && !self.approvers.is_empty()
+        }
+        
+        pub fn required_approvals(&self) -> u32 {
+            if self.approvers.is_empty() {
+                1
+            } else {
+                self.approval_threshold as u32
+            }
+        }
+
+        /// Scales the milestone schedule so it sums to `amount`, keeping each
+        /// milestone's share of the budget.
+        pub fn scaled_milestone_amounts(&self, amount: u64) -> Result<Vec<u64>> {
+            let amounts: Vec<u64> = self.milestones.iter().map(|m| m.amount).collect();
+            scale_amounts(&amounts, self.budget, amount)
+        }
+
+        /// Accepts `bid`, rewriting the milestone schedule so milestone payouts
+        /// sum to what gets escrowed.
+        pub fn accept(
+            &mut self,
+            bid_key: Pubkey,
+            bid: &mut Bid,
+            stake: Option<&mut StakeVault>,
+            now: i64,
+        ) -> Result<()> {
+            require!(now <= bid.expires_at, AgoraError::BidExpired);
+            require!(bid.scope_version == self.scope_version, AgoraError::StaleBid);
+            require!(
+                bid.milestone_amounts.len() == self.milestones.len(),
+                AgoraError::BidMilestoneMismatch
+            );
+            
+            // The bidder's stake stays locked until the contract ends.
+            if self.requirements.min_stake > 0 {
+                let stake = stake.ok_or(AgoraError::InsufficientStake)?;
+                require!(
+                    stake.available() >= self.requirements.min_stake,
+                    AgoraError::InsufficientStake
+                );
+                stake.locked_contracts = stake
+                    .locked_contracts
+                    .checked_add(1)
+                    .ok_or(AgoraError::MathOverflow)?;
+                bid.stake_locked = true;
+            }
+            
+            for (milestone, amount) in self.milestones.iter_mut().zip(bid.milestone_amounts.iter()) {
+                milestone.amount = *amount;
+            }
+            self.budget = bid.amount;
+            
+            bid.status = BidStatus::Accepted;
+            bid.accepted_at = now;
+            self.accepted_bid = Some(bid_key);
+            self.status = TaskStatus::InProgress;
+            self.updated_at = now;
+            Ok(())
+        }
+    }
+
+    /// Scales `amounts` (which sum to `total`) so they sum to `target`.
+    /// Rounding dust goes to the last entry.
+    pub fn scale_amounts(amounts: &[u64], total: u64, target: u64) -> Result<Vec<u64>> {
+        let count = amounts.len();
+        let mut scaled = Vec::with_capacity(count);
+        let mut allocated: u64 = 0;
+
+        for amount in amounts.iter().take(count.saturating_sub(1)) {
+            let share = if total == 0 {
+                0
+            } else {
+                (*amount as u128 * target as u128 / total as u128) as u64
+            };
+            allocated = allocated.checked_add(share).ok_or(AgoraError::MathOverflow)?;
+            scaled.push(share);
+        }
+
+        if count > 0 {
+            scaled.push(target.checked_sub(allocated).ok_or(AgoraError::MathOverflow)?);
+        }
+
+        Ok(scaled)
     }
 
     #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -38,10 +255,44 @@ pub mod state {
         pub amount: u64,
         pub completed: bool,
         pub paid: bool,
+        /// Bitmask of approvals, indexed like `Task::approvers`.
+        pub approvals: u8,
+    }
+
+    /// A change to an open task's milestone schedule.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+    pub enum MilestoneOp {
+        Add { description: String, amount: u64 },
+        Edit { index: u8, description: Option<String>, amount: Option<u64> },
+        Remove { index: u8 },
     }
 
     impl Milestone {
-        pub const SIZE: usize = 4 + 200 + 8 + 1 + 1;
+        pub const MAX_DESC_LEN: usize = 200;
+        
+        pub fn space(&self) -> usize {
+            4 + self.description.len() + 8 + 1 + 1 + 1
+        }
+    }
+
+    /// Pointer to a task description stored off-chain.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+    pub struct TaskMetadata {
+        pub uri: String,
+        /// BLAKE3 hash of the document at `uri`.
+        pub content_hash: [u8; 32],
+    }
+
+    impl TaskMetadata {
+        pub const MAX_URI_LEN: usize = 200;
+        
+        pub fn space(&self) -> usize {
+            4 + self.uri.len() + 32
+        }
+        
+        pub fn is_valid(&self) -> bool {
+            !self.uri.is_empty() && self.uri.len() <= Self::MAX_URI_LEN
+        }
     }
 
     #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
@@ -51,6 +302,43 @@ pub mod state {
         Completed,
         Cancelled,
         Disputed,
+        Expired,
+    }
+
+    /// Conditions a bidder has to meet before `submit_bid` accepts their bid.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+    pub struct BidRequirements {
+        /// Lamports locked in a `BidBond` PDA for every bid. Zero disables bonds.
+        pub bid_bond: u64,
+        pub visibility: TaskVisibility,
+        pub min_tasks_completed: u32,
+        /// Minimum average rating (1-5). Zero disables the check.
+        pub min_average_rating: u8,
+        /// Skill tags the bidder's profile must declare.
+        pub required_skills: Vec<String>,
+        /// Endorsements the bidder needs on each required skill.
+        pub min_skill_endorsements: u32,
+        /// Lamports the bidder must have staked. Zero disables the check.
+        pub min_stake: u64,
+    }
+
+    impl BidRequirements {
+        pub const MAX_REQUIRED_SKILLS: usize = 3;
+        pub const SIZE: usize = 8 + 1 + 4 + 1
+            + 4 + Self::MAX_REQUIRED_SKILLS * (4 + AgentProfile::MAX_SKILL_TAG_LEN)
+            + 4 + 8;
+        
+        pub fn has_reputation_gate(&self) -> bool {
+            self.min_tasks_completed > 0 || self.min_average_rating > 0
+        }
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+    pub enum TaskVisibility {
+        #[default]
+        Public,
+        /// Only pubkeys on the task's `invitees` list may bid.
+        InviteOnly,
     }
 
     #[account]
@@ -60,18 +348,50 @@ pub mod state {
         pub amount: u64,
         pub timeline: i64,
         pub proposal: String,
+        pub milestone_amounts: Vec<u64>,
+        pub offers: Vec<Offer>,
+        pub bond: u64,
         pub status: BidStatus,
         pub created_at: i64,
+        pub accepted_at: i64,
+        pub expires_at: i64,
+        /// Whether accepting this bid locked the bidder's stake.
+        pub stake_locked: bool,
+        /// Task scope the bid was made against.
+        pub scope_version: u32,
+        /// When the winner first completed a milestone; zero until then.
+        pub delivered_at: i64,
     }
 
     impl Bid {
         pub const MAX_PROPOSAL_LEN: usize = 2000;
+        pub const MAX_OFFERS: usize = 6;
         
         pub fn space() -> usize {
-            8 + 32 + 32 + 8 + 8 + 4 + Self::MAX_PROPOSAL_LEN + 1 + 8
+            8 + 32 + 32 + 8 + 8 + 4 + Self::MAX_PROPOSAL_LEN +
+            4 + (Task::MAX_MILESTONES * 8) + 4 + (Self::MAX_OFFERS * Offer::SIZE) +
+            8 + 1 + 8 + 8 + 8 + 1 + 4 + 8
+        }
+        
+        /// Whether the winner completed a milestone within the bid's timeline.
+        pub fn delivered_on_time(&self) -> bool {
+            self.delivered_at != 0 && self.delivered_at <= self.accepted_at + self.timeline
         }
     }
 
+    /// A counter-offer made by either party while negotiating a pending bid.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+    pub struct Offer {
+        pub amount: u64,
+        pub timeline: i64,
+        pub proposed_by: Pubkey,
+        pub created_at: i64,
+    }
+
+    impl Offer {
+        pub const SIZE: usize = 8 + 8 + 32 + 8;
+    }
+
     #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
     pub enum BidStatus {
         Pending,
@@ -80,6 +400,18 @@ pub mod state {
         Withdrawn,
     }
 
+    #[account]
+    pub struct BidBond {
+        pub bid: Pubkey,
+        pub bidder: Pubkey,
+        pub amount: u64,
+        pub bump: u8,
+    }
+
+    impl BidBond {
+        pub const SIZE: usize = 8 + 32 + 32 + 8 + 1;
+    }
+
     #[account]
     pub struct Escrow {
         pub task: Pubkey,
@@ -97,22 +429,530 @@ pub mod state {
 
     #[account]
     pub struct AgentProfile {
+        /// Layout version. Fields are only ever appended, so an account written
+        /// at an older version is a prefix of the current layout and is brought
+        /// up to date with `migrate_agent_profile`.
+        pub version: u8,
+        pub owner: Pubkey,
+        pub name: String,
+        pub bio_uri: String,
+        pub contact_uri: String,
+        pub skill_tags: Vec<String>,
+        pub tasks_posted: u32,
+        pub tasks_completed: u32,
+        pub total_earned: u64,
+        pub total_spent: u64,
+        pub rating_sum: u32,
+        pub rating_count: u32,
+        /// Decayed sum of `rating * escrowed amount`, scaled by `REPUTATION_SCALE`.
+        pub weighted_rating_sum: u128,
+        /// Decayed sum of escrowed amounts, scaled by `REPUTATION_SCALE`.
+        pub weighted_amount: u128,
+        pub reputation_updated_at: i64,
+        /// Posted tasks that have not yet completed, been cancelled or expired.
+        pub open_tasks: u32,
+        /// Funded escrows this profile is a party to, as client or freelancer.
+        pub active_escrows: u32,
+        pub created_at: i64,
+        // Version 2
+        /// Endorsement count per entry in `skill_tags`.
+        pub skill_endorsements: Vec<u32>,
+        pub endorsement_count: u32,
+        /// Funded escrows where this profile is the freelancer.
+        pub active_contracts: u32,
+        pub disputes_lost: u32,
+        /// Profile that referred this agent, if any.
+        pub referrer: Option<Pubkey>,
+        /// Whether the referral has been settled on this agent's first completed task.
+        pub referral_paid: bool,
+    }
+
+    /// `AgentProfile` as written at version 1, read by `migrate_agent_profile`.
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    pub struct AgentProfileV1 {
+        pub version: u8,
         pub owner: Pubkey,
         pub name: String,
+        pub bio_uri: String,
+        pub contact_uri: String,
+        pub skill_tags: Vec<String>,
         pub tasks_posted: u32,
         pub tasks_completed: u32,
         pub total_earned: u64,
         pub total_spent: u64,
         pub rating_sum: u32,
         pub rating_count: u32,
+        pub weighted_rating_sum: u128,
+        pub weighted_amount: u128,
+        pub reputation_updated_at: i64,
+        pub open_tasks: u32,
+        pub active_escrows: u32,
         pub created_at: i64,
     }
 
     impl AgentProfile {
+        pub const VERSION: u8 = 2;
         pub const MAX_NAME_LEN: usize = 50;
+        pub const MAX_URI_LEN: usize = 200;
+        pub const MAX_SKILL_TAGS: usize = 10;
+        pub const MAX_SKILL_TAG_LEN: usize = 32;
+        pub const REPUTATION_SCALE: u128 = 1_000_000;
+        /// Ratings lose half their weight every 180 days.
+        pub const REPUTATION_HALF_LIFE: i64 = 180 * 24 * 60 * 60;
         
         pub fn space() -> usize {
-            8 + 32 + 4 + Self::MAX_NAME_LEN + 4 + 4 + 8 + 8 + 4 + 4 + 8
+            Self::space_with(0, 0, &[])
+        }
+        
+        /// Account size for the given metadata. The name is always reserved at
+        /// its maximum length; URIs and skill tags are sized to fit.
+        pub fn space_with(bio_uri_len: usize, contact_uri_len: usize, skill_tags: &[String]) -> usize {
+            let tags: usize = skill_tags.iter().map(|t| 4 + t.len()).sum();
+            8 + 1 + 32 + 4 + Self::MAX_NAME_LEN + 4 + bio_uri_len + 4 + contact_uri_len + 4 + tags
+                + 4 + 4 + 8 + 8 + 4 + 4 + 16 + 16 + 8 + 4 + 4 + 8
+                + 4 + skill_tags.len() * 4 + 4 + 4 + 4 + 33 + 1
+        }
+        
+        /// Size of the account as it currently stands.
+        pub fn current_space(&self) -> usize {
+            Self::space_with(self.bio_uri.len(), self.contact_uri.len(), &self.skill_tags)
+        }
+        
+        /// Tags are lowercase ASCII letters, digits and dashes so that the same
+        /// skill always hashes to the same `SkillIndex`.
+        pub fn is_valid_skill_tag(tag: &str) -> bool {
+            !tag.is_empty()
+                && tag.len() <= Self::MAX_SKILL_TAG_LEN
+                && tag
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        }
+        
+        pub fn has_skills(&self, skills: &[String]) -> bool {
+            skills.iter().all(|s| self.skill_tags.contains(s))
+        }
+        
+        pub fn skill_endorsements(&self, skill: &str) -> u32 {
+            self.skill_tags
+                .iter()
+                .position(|t| t == skill)
+                .map_or(0, |i| self.skill_endorsements[i])
+        }
+        
+        pub fn has_endorsements(&self, skills: &[String], min: u32) -> bool {
+            skills.iter().all(|s| self.skill_endorsements(s) >= min)
+        }
+        
+        pub fn has_open_work(&self) -> bool {
+            self.open_tasks > 0 || self.active_escrows > 0
+        }
+        
+        /// Applies `elapsed` seconds of decay to a fixed-point value. Whole half-lives
+        /// are exact; the remainder is interpolated linearly between 1 and 1/2.
+        pub fn decay(value: u128, elapsed: i64) -> u128 {
+            if elapsed <= 0 {
+                return value;
+            }
+            let half_life = Self::REPUTATION_HALF_LIFE as u128;
+            let halvings = elapsed as u128 / half_life;
+            if halvings >= 128 {
+                return 0;
+            }
+            let remainder = elapsed as u128 % half_life;
+            let halved = value >> halvings;
+            match halved.checked_mul(2 * half_life - remainder) {
+                Some(v) => v / (2 * half_life),
+                None => halved / (2 * half_life) * (2 * half_life - remainder),
+            }
+        }
+        
+        /// Returns `(weighted_rating_sum, weighted_amount)` decayed to `now`.
+        pub fn decayed_reputation(&self, now: i64) -> (u128, u128) {
+            let elapsed = now - self.reputation_updated_at;
+            (
+                Self::decay(self.weighted_rating_sum, elapsed),
+                Self::decay(self.weighted_amount, elapsed),
+            )
+        }
+        
+        /// Folds a rating weighted by `amount` into the decayed reputation.
+        pub fn record_rating(&mut self, rating: u8, amount: u64, now: i64) -> Result<()> {
+            let (rating_sum, weight_sum) = self.decayed_reputation(now);
+            let weight = (amount as u128)
+                .checked_mul(Self::REPUTATION_SCALE)
+                .ok_or(AgoraError::MathOverflow)?;
+            
+            self.weighted_rating_sum = weight
+                .checked_mul(rating as u128)
+                .and_then(|w| w.checked_add(rating_sum))
+                .ok_or(AgoraError::MathOverflow)?;
+            self.weighted_amount = weight_sum
+                .checked_add(weight)
+                .ok_or(AgoraError::MathOverflow)?;
+            self.reputation_updated_at = now;
+            Ok(())
+        }
+        
+        /// Takes back a rating recorded at `rated_at`, at its decayed value.
+        pub fn remove_rating(&mut self, rating: u8, amount: u64, rated_at: i64, now: i64) -> Result<()> {
+            let (rating_sum, weight_sum) = self.decayed_reputation(now);
+            let weight = (amount as u128)
+                .checked_mul(Self::REPUTATION_SCALE)
+                .ok_or(AgoraError::MathOverflow)?;
+            let weighted_rating = weight
+                .checked_mul(rating as u128)
+                .ok_or(AgoraError::MathOverflow)?;
+            
+            // Rounding in the decay can leave the sums slightly below the
+            // review's own decayed share.
+            self.weighted_rating_sum = rating_sum
+                .saturating_sub(Self::decay(weighted_rating, now - rated_at));
+            self.weighted_amount = weight_sum.saturating_sub(Self::decay(weight, now - rated_at));
+            self.reputation_updated_at = now;
+            
+            self.rating_sum = self
+                .rating_sum
+                .checked_sub(rating as u32)
+                .ok_or(AgoraError::MathOverflow)?;
+            self.rating_count = self
+                .rating_count
+                .checked_sub(1)
+                .ok_or(AgoraError::MathOverflow)?;
+            Ok(())
+        }
+        
+        /// Value-weighted average rating at `now`, scaled by `REPUTATION_SCALE`.
+        pub fn reputation_score(&self, now: i64) -> u64 {
+            let (rating_sum, weight_sum) = self.decayed_reputation(now);
+            if weight_sum == 0 {
+                return 0;
+            }
+            match rating_sum.checked_mul(Self::REPUTATION_SCALE) {
+                Some(v) => (v / weight_sum) as u64,
+                None => (rating_sum / (weight_sum / Self::REPUTATION_SCALE)) as u64,
+            }
+        }
+        
+        pub fn meets_reputation(&self, requirements: &BidRequirements) -> bool {
+            if self.tasks_completed < requirements.min_tasks_completed {
+                return false;
+            }
+            if requirements.min_average_rating > 0 {
+                // rating_sum / rating_count >= min, without integer division
+                let required = requirements.min_average_rating as u64 * self.rating_count as u64;
+                if self.rating_count == 0 || (self.rating_sum as u64) < required {
+                    return false;
+                }
+            }
+            true
+        }
+    }
+
+    /// Read-only snapshot returned by `get_reputation`.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+    pub struct ReputationView {
+        pub profile: Pubkey,
+        /// Value-weighted, time-decayed average rating scaled by `REPUTATION_SCALE`.
+        pub score: u64,
+        /// Decayed escrow volume behind the score, scaled by `REPUTATION_SCALE`.
+        pub weighted_amount: u128,
+        pub rating_count: u32,
+        pub as_of: i64,
+    }
+
+    /// Lists profiles declaring a skill tag, at `[b"skill", sha256(tag)]`.
+    /// The account grows as profiles are added, up to `MAX_PROFILES`; once it
+    /// is full, further profiles still carry the tag but are not listed.
+    #[account]
+    pub struct SkillIndex {
+        pub tag: String,
+        pub profiles: Vec<Pubkey>,
+        pub bump: u8,
+    }
+
+    impl SkillIndex {
+        /// Kept small enough that deserializing the list stays well inside the
+        /// 32 KiB program heap; larger lists would lock the index for removals too.
+        pub const MAX_PROFILES: usize = 128;
+        
+        pub fn tag_hash(tag: &str) -> [u8; 32] {
+            hash(tag.as_bytes()).to_bytes()
+        }
+        
+        pub fn space(profile_count: usize) -> usize {
+            8 + 4 + AgentProfile::MAX_SKILL_TAG_LEN + 4 + profile_count * 32 + 1
+        }
+    }
+
+    /// A client's endorsement of the freelancer on one skill, backed by a
+    /// completed task. At `[b"endorsement", task, sha256(skill)]`.
+    #[account]
+    pub struct Endorsement {
+        pub endorser: Pubkey,
+        pub endorsee: Pubkey,
+        pub task: Pubkey,
+        pub skill: String,
+        pub created_at: i64,
+        pub bump: u8,
+    }
+
+    impl Endorsement {
+        pub const SIZE: usize = 8 + 32 + 32 + 32 + 4 + AgentProfile::MAX_SKILL_TAG_LEN + 8 + 1;
+    }
+
+    /// A hot key authorized to act for a profile owner, at
+    /// `[b"delegation", profile, delegate]`. Closing it revokes the key.
+    #[account]
+    pub struct Delegation {
+        pub profile: Pubkey,
+        pub owner: Pubkey,
+        pub delegate: Pubkey,
+        /// Bitmask of `Delegation::SUBMIT_BID` etc.
+        pub permissions: u8,
+        /// Largest bid amount the delegate may submit.
+        pub max_bid_amount: u64,
+        pub expires_at: i64,
+        pub created_at: i64,
+        pub bump: u8,
+    }
+
+    impl Delegation {
+        pub const SUBMIT_BID: u8 = 1 << 0;
+        pub const COMPLETE_MILESTONE: u8 = 1 << 1;
+        pub const SUBMIT_EVIDENCE: u8 = 1 << 2;
+        pub const ALL_PERMISSIONS: u8 = Self::SUBMIT_BID | Self::COMPLETE_MILESTONE | Self::SUBMIT_EVIDENCE;
+        
+        pub const SIZE: usize = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 1;
+        
+        pub fn authorize(&self, permission: u8, now: i64) -> Result<()> {
+            require!(self.permissions & permission != 0, AgoraError::DelegationNotPermitted);
+            require!(now < self.expires_at, AgoraError::DelegationExpired);
+            Ok(())
+        }
+    }
+
+    /// Bidders a client refuses to work with, at `[b"blocklist", owner]`.
+    #[account]
+    pub struct Blocklist {
+        pub owner: Pubkey,
+        pub blocked: Vec<Pubkey>,
+        pub bump: u8,
+    }
+
+    impl Blocklist {
+        pub const MAX_BLOCKED: usize = 100;
+        pub const SIZE: usize = 8 + 32 + 4 + Self::MAX_BLOCKED * 32 + 1;
+    }
+
+    /// Lamports an agent puts behind their reputation, held at `[b"stake", profile]`.
+    #[account]
+    pub struct StakeVault {
+        pub profile: Pubkey,
+        pub owner: Pubkey,
+        /// Staked lamports, excluding the account's rent.
+        pub amount: u64,
+        /// Part of `amount` queued for withdrawal. Still slashable.
+        pub pending_unstake: u64,
+        pub unstake_requested_at: i64,
+        /// Accepted bids backed by this stake.
+        pub locked_contracts: u32,
+        pub bump: u8,
+    }
+
+    impl StakeVault {
+        pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 4 + 1;
+        pub const UNSTAKE_COOLDOWN: i64 = 7 * 24 * 60 * 60;
+        
+        /// Stake that counts towards bidding requirements.
+        pub fn available(&self) -> u64 {
+            self.amount.saturating_sub(self.pending_unstake)
+        }
+        
+        pub fn unlock(&mut self) {
+            self.locked_contracts = self.locked_contracts.saturating_sub(1);
+        }
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+    pub enum DisputeRuling {
+        /// Remaining escrow is refunded and the freelancer's stake is slashed.
+        Client,
+        /// Remaining escrow is released to the freelancer.
+        Freelancer,
+    }
+
+    /// A dispute over an in-progress task, at `[b"dispute", task]`.
+    #[account]
+    pub struct Dispute {
+        pub task: Pubkey,
+        pub raised_by: Pubkey,
+        pub reason: String,
+        pub raised_at: i64,
+        pub ruling: Option<DisputeRuling>,
+        pub slashed: u64,
+        pub resolved_by: Option<Pubkey>,
+        pub resolved_at: i64,
+        pub bump: u8,
+    }
+
+    impl Dispute {
+        pub const MAX_REASON_LEN: usize = 500;
+        pub const SIZE: usize = 8 + 32 + 32 + 4 + Self::MAX_REASON_LEN + 8 + 2 + 8 + 33 + 8 + 1;
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+    pub enum TaskCategory {
+        Development,
+        Design,
+        Writing,
+        Research,
+        DataLabeling,
+        Audit,
+        Marketing,
+        Other,
+    }
+
+    impl TaskCategory {
+        pub const COUNT: usize = 8;
+    }
+
+    /// Page new tasks in a category go on, at `[b"category_head", category]`.
+    /// Pages `0..next_page` hold every open task; full pages that lose a task
+    /// are queued in `free_pages` and filled again before a new page is opened.
+    #[account]
+    pub struct CategoryHead {
+        pub category: TaskCategory,
+        pub current_page: u32,
+        /// Lowest page that has never been used.
+        pub next_page: u32,
+        /// Pages other than `current_page` with room for another task.
+        pub free_pages: Vec<u32>,
+        pub bump: u8,
+    }
+
+    impl CategoryHead {
+        /// Holes beyond this many pages are left unused rather than tracked.
+        pub const MAX_FREE_PAGES: usize = 64;
+        pub const SIZE: usize = 8 + 1 + 4 + 4 + 4 + Self::MAX_FREE_PAGES * 4 + 1;
+        
+        /// Moves `current_page` on once it has filled up, preferring a page
+        /// with a hole over opening a new one.
+        pub fn advance(&mut self) -> Result<()> {
+            self.current_page = match self.free_pages.pop() {
+                Some(page) => page,
+                None => {
+                    let page = self.next_page;
+                    self.next_page = page.checked_add(1).ok_or(AgoraError::MathOverflow)?;
+                    page
+                }
+            };
+            Ok(())
+        }
+    }
+
+    /// One page of open tasks in a category, at `[b"category", category, page]`.
+    #[account]
+    pub struct CategoryIndex {
+        pub category: TaskCategory,
+        pub page: u32,
+        pub tasks: Vec<Pubkey>,
+        pub bump: u8,
+    }
+
+    impl CategoryIndex {
+        pub const MAX_TASKS: usize = 100;
+        pub const SIZE: usize = 8 + 1 + 4 + 4 + Self::MAX_TASKS * 32 + 1;
+        
+        /// Drops `task` from the page once it is no longer open, and offers the
+        /// slot back to the head if the page was full.
+        pub fn remove(&mut self, task: &Pubkey, head: &mut CategoryHead) {
+            let was_full = self.tasks.len() == Self::MAX_TASKS;
+            self.tasks.retain(|k| k != task);
+            if was_full
+                && self.tasks.len() < Self::MAX_TASKS
+                && self.page != head.current_page
+                && head.free_pages.len() < CategoryHead::MAX_FREE_PAGES
+            {
+                head.free_pages.push(self.page);
+            }
+        }
+    }
+
+    /// Lowest amount per milestone the agent will bid for in a given mint.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+    pub struct MintPrice {
+        pub mint: Pubkey,
+        pub min_amount: u64,
+    }
+
+    impl MintPrice {
+        pub const SIZE: usize = 32 + 8;
+    }
+
+    /// Fields an owner sets on their `AgentManifest`.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+    pub struct ManifestParams {
+        pub categories: Vec<TaskCategory>,
+        /// Zero means no limit.
+        pub max_concurrent_tasks: u16,
+        pub min_milestone_amount: u64,
+        pub mint_pricing: Vec<MintPrice>,
+        pub available: bool,
+        /// Hash of the extended off-chain manifest document.
+        pub extended_manifest_hash: [u8; 32],
+    }
+
+    /// Structured capabilities for automated matching, at `[b"manifest", profile]`.
+    #[account]
+    pub struct AgentManifest {
+        pub profile: Pubkey,
+        pub owner: Pubkey,
+        /// Layout version of this account.
+        pub schema_version: u8,
+        /// Bumped on every update so readers can detect changes.
+        pub revision: u32,
+        pub categories: Vec<TaskCategory>,
+        pub max_concurrent_tasks: u16,
+        pub min_milestone_amount: u64,
+        pub mint_pricing: Vec<MintPrice>,
+        pub available: bool,
+        pub extended_manifest_hash: [u8; 32],
+        pub updated_at: i64,
+        pub bump: u8,
+    }
+
+    impl AgentManifest {
+        pub const SCHEMA_VERSION: u8 = 1;
+        pub const MAX_MINT_PRICES: usize = 4;
+        pub const SIZE: usize = 8 + 32 + 32 + 1 + 4
+            + 4 + TaskCategory::COUNT
+            + 2 + 8
+            + 4 + Self::MAX_MINT_PRICES * MintPrice::SIZE
+            + 1 + 32 + 8 + 1;
+        
+        pub fn apply(&mut self, params: ManifestParams, now: i64) -> Result<()> {
+            require!(
+                params.categories.len() <= TaskCategory::COUNT,
+                AgoraError::TooManyCategories
+            );
+            require!(
+                params.mint_pricing.len() <= Self::MAX_MINT_PRICES,
+                AgoraError::TooManyMintPrices
+            );
+            
+            self.schema_version = Self::SCHEMA_VERSION;
+            self.categories = params.categories;
+            self.max_concurrent_tasks = params.max_concurrent_tasks;
+            self.min_milestone_amount = params.min_milestone_amount;
+            self.mint_pricing = params.mint_pricing;
+            self.available = params.available;
+            self.extended_manifest_hash = params.extended_manifest_hash;
+            self.updated_at = now;
+            Ok(())
+        }
+        
+        pub fn has_capacity(&self, active_contracts: u32) -> bool {
+            self.max_concurrent_tasks == 0 || active_contracts < self.max_concurrent_tasks as u32
         }
     }
 
@@ -122,17 +962,116 @@ pub mod state {
         pub reviewee: Pubkey,
         pub task: Pubkey,
         pub rating: u8,
+        pub weight: u64,
         pub review_text: String,
         pub created_at: i64,
+        /// The reviewee's one reply, empty until they respond.
+        pub response: String,
+        pub responded_at: i64,
+        /// Set when an arbiter or the admin hides the review from the profile aggregate.
+        pub flagged_by: Option<Pubkey>,
+        pub flagged_at: i64,
     }
 
     impl Review {
         pub const MAX_REVIEW_LEN: usize = 1000;
+        pub const MAX_RESPONSE_LEN: usize = 500;
+        
+        pub fn space() -> usize {
+            8 + 32 + 32 + 32 + 1 + 8 + 4 + Self::MAX_REVIEW_LEN + 8
+                + 4 + Self::MAX_RESPONSE_LEN + 8 + 33 + 8
+        }
+    }
+
+    /// Marketplace-wide settings at `[b"config"]`.
+    #[account]
+    pub struct MarketplaceConfig {
+        pub admin: Pubkey,
+        /// May flag reviews alongside the admin.
+        pub arbiter: Pubkey,
+        /// Owner of the token accounts that receive protocol fees.
+        pub treasury: Pubkey,
+        /// Fee taken from each milestone payout, in basis points.
+        pub protocol_fee_bps: u16,
+        /// Share of the protocol fee paid to a referrer, in basis points of the fee.
+        pub referral_share_bps: u16,
+        pub bump: u8,
+    }
+
+    impl MarketplaceConfig {
+        pub const SIZE: usize = 8 + 32 + 32 + 32 + 2 + 2 + 1;
+        
+        pub fn protocol_fee(&self, amount: u64) -> u64 {
+            (amount as u128 * self.protocol_fee_bps as u128 / 10_000) as u64
+        }
+        
+        pub fn referral_reward(&self, fee: u64) -> u64 {
+            (fee as u128 * self.referral_share_bps as u128 / 10_000) as u64
+        }
+        
+        pub fn is_moderator(&self, key: &Pubkey) -> bool {
+            *key == self.admin || *key == self.arbiter
+        }
+    }
+
+    /// Profile thresholds a badge holder must meet. Zero disables a check.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+    pub struct BadgeCriteria {
+        pub min_tasks_completed: u32,
+        pub min_total_earned: u64,
+        pub min_average_rating: u8,
+        /// Maximum disputes lost, if the badge requires a clean record.
+        pub max_disputes_lost: Option<u32>,
+    }
+
+    impl BadgeCriteria {
+        pub const SIZE: usize = 4 + 8 + 1 + 1 + 4;
+        
+        pub fn is_met_by(&self, profile: &AgentProfile) -> bool {
+            let rating_ok = self.min_average_rating == 0 || (
+                profile.rating_count > 0 &&
+                profile.rating_sum as u64 >= self.min_average_rating as u64 * profile.rating_count as u64
+            );
+            profile.tasks_completed >= self.min_tasks_completed &&
+                profile.total_earned >= self.min_total_earned &&
+                rating_ok &&
+                self.max_disputes_lost.is_none_or(|max| profile.disputes_lost <= max)
+        }
+    }
+
+    /// A badge type defined by the config admin, at `[b"badge_definition", id]`.
+    #[account]
+    pub struct BadgeDefinition {
+        pub id: u32,
+        pub name: String,
+        pub uri: String,
+        pub criteria: BadgeCriteria,
+        pub issued: u32,
+        pub bump: u8,
+    }
+
+    impl BadgeDefinition {
+        pub const MAX_NAME_LEN: usize = 32;
         
         pub fn space() -> usize {
-            8 + 32 + 32 + 32 + 1 + 4 + Self::MAX_REVIEW_LEN + 8
+            8 + 4 + 4 + Self::MAX_NAME_LEN + 4 + AgentProfile::MAX_URI_LEN + BadgeCriteria::SIZE + 4 + 1
         }
     }
+
+    /// A badge held by a profile, at `[b"badge", definition, profile]`.
+    /// There is no instruction to move it, so it cannot be transferred.
+    #[account]
+    pub struct Badge {
+        pub definition: Pubkey,
+        pub profile: Pubkey,
+        pub owner: Pubkey,
+        pub awarded_at: i64,
+        pub bump: u8,
+    }
+
+    impl Badge {
+        pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 1;
+    }
 }
 
 use state::*;
@@ -177,10 +1116,170 @@ pub enum AgoraError {
     ReviewTooLong,
     #[msg("Name too long")]
     NameTooLong,
+    #[msg("Bid milestone schedule does not match the task's milestones")]
+    BidMilestoneMismatch,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Bid bond account does not match the task's bond requirement")]
+    BidBondMismatch,
+    #[msg("Bid bond cannot be reclaimed yet")]
+    BidBondLocked,
+    #[msg("Bid bond cannot be forfeited")]
+    BidBondNotForfeitable,
+    #[msg("Task deadline has not passed")]
+    TaskNotExpired,
+    #[msg("Bid has expired")]
+    BidExpired,
+    #[msg("Bid has not expired")]
+    BidNotExpired,
+    #[msg("Signer is not a party to this bid")]
+    NotBidParty,
+    #[msg("Too many offers on this bid")]
+    TooManyOffers,
+    #[msg("Bid has no counter-offer")]
+    NoCounterOffer,
+    #[msg("Cannot accept your own offer")]
+    CannotAcceptOwnOffer,
+    #[msg("Bidder is not invited to this task")]
+    NotInvited,
+    #[msg("Too many invitees")]
+    TooManyInvitees,
+    #[msg("Bidder is already invited")]
+    AlreadyInvited,
+    #[msg("Invitee not found")]
+    InviteeNotFound,
+    #[msg("Bidder does not meet the task's reputation requirements")]
+    InsufficientReputation,
+    #[msg("Reviewee must be the other party on the task")]
+    InvalidReviewee,
+    #[msg("URI too long")]
+    UriTooLong,
+    #[msg("Too many skill tags (max 10)")]
+    TooManySkillTags,
+    #[msg("Skill tags must be 1-32 lowercase letters, digits or dashes")]
+    InvalidSkillTag,
+    #[msg("Profile has open tasks or active escrows")]
+    ProfileHasOpenWork,
+    #[msg("Skill tag already on profile")]
+    DuplicateSkillTag,
+    #[msg("Skill tag not found")]
+    SkillTagNotFound,
+    #[msg("Too many required skills (max 3)")]
+    TooManyRequiredSkills,
+    #[msg("Bidder's profile is missing a required skill")]
+    MissingRequiredSkill,
+    #[msg("Bidder does not have enough endorsements on a required skill")]
+    InsufficientEndorsements,
+    #[msg("Signer is not authorized")]
+    Unauthorized,
+    #[msg("Profile does not meet the badge criteria")]
+    BadgeCriteriaNotMet,
+    #[msg("Response cannot be empty")]
+    EmptyResponse,
+    #[msg("Response too long")]
+    ResponseTooLong,
+    #[msg("Review already has a response")]
+    AlreadyResponded,
+    #[msg("Review is already flagged")]
+    ReviewAlreadyFlagged,
+    #[msg("Invalid delegation permissions")]
+    InvalidPermissions,
+    #[msg("Delegation does not grant this permission")]
+    DelegationNotPermitted,
+    #[msg("Delegation has expired")]
+    DelegationExpired,
+    #[msg("Amount exceeds the delegation's bid cap")]
+    DelegationCapExceeded,
+    #[msg("Too many task categories")]
+    TooManyCategories,
+    #[msg("Too many mint pricing entries (max 4)")]
+    TooManyMintPrices,
+    #[msg("Agent is not accepting work")]
+    AgentUnavailable,
+    #[msg("Agent is at its concurrent task limit")]
+    ConcurrencyLimitReached,
+    #[msg("Bidder does not have enough stake")]
+    InsufficientStake,
+    #[msg("Stake is locked by an accepted bid")]
+    StakeLocked,
+    #[msg("Unstake cooldown has not passed")]
+    UnstakeCooldown,
+    #[msg("No unstake pending")]
+    NoPendingUnstake,
+    #[msg("Stake account does not match the bid")]
+    StakeMismatch,
+    #[msg("Dispute reason too long")]
+    DisputeReasonTooLong,
+    #[msg("Slash must be at most 10000 basis points")]
+    InvalidSlash,
+    #[msg("Fees must be at most 10000 basis points")]
+    InvalidFee,
+    #[msg("Treasury token account required when a protocol fee is set")]
+    MissingTreasuryAccount,
+    #[msg("Referrer accounts required to settle the referral")]
+    MissingReferrerAccount,
+    #[msg("Bidder is blocked by the task owner")]
+    BidderBlocked,
+    #[msg("Bidder already blocked")]
+    AlreadyBlocked,
+    #[msg("Bidder not blocked")]
+    NotBlocked,
+    #[msg("Too many blocked bidders")]
+    BlocklistFull,
+    #[msg("Category index page is full")]
+    CategoryIndexFull,
+    #[msg("Compact tasks keep their description off-chain")]
+    InlineDescriptionOnCompactTask,
+    #[msg("Task metadata URI is empty or too long")]
+    InvalidTaskMetadata,
+    #[msg("Task stores its description inline")]
+    TaskNotCompact,
+    #[msg("Milestone description too long")]
+    MilestoneDescriptionTooLong,
+    #[msg("Task scope changed since the bid was made")]
+    StaleBid,
+    #[msg("Signer is not the proposed task owner")]
+    NotPendingOwner,
+    #[msg("Escrow account must be provided for funded tasks")]
+    MissingEscrowAccount,
+    #[msg("Too many approvers")]
+    TooManyApprovers,
+    #[msg("Duplicate approver")]
+    DuplicateApprover,
+    #[msg("Approval threshold must be between 1 and the number of approvers")]
+    InvalidApprovalThreshold,
+    #[msg("Signer is not an approver for this task")]
+    NotApprover,
+    #[msg("Milestone already approved by this signer")]
+    AlreadyApproved,
+    #[msg("No approval to revoke")]
+    ApprovalNotFound,
+    #[msg("Remove the profile's skill tags before closing it")]
+    ProfileHasSkillTags,
+    #[msg("Referrer token account is not owned by the referrer")]
+    InvalidReferrerTokenAccount,
+    #[msg("Tasks must be posted on the category's current page")]
+    StaleCategoryPage,
+    #[msg("Profile must be migrated to the current layout first")]
+    ProfileNeedsMigration,
+    #[msg("Profile is already at the current layout version")]
+    ProfileAlreadyMigrated,
+    #[msg("Release approvers cannot be changed once escrow is funded")]
+    ApproversLocked,
 }
 
 #[derive(Accounts)]
-#[instruction(title: String, description: String, milestones: Vec<Milestone>)]
+#[instruction(
+    title: String,
+    description: String,
+    budget: u64,
+    milestones: Vec<Milestone>,
+    deadline: i64,
+    requirements: Option<BidRequirements>,
+    category: TaskCategory,
+    category_page: u32,
+    metadata: Option<TaskMetadata>
+)]
 pub struct PostTask<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -188,14 +1287,47 @@ pub struct PostTask<'info> {
     #[account(
         init,
         payer = owner,
-        space = Task::space(milestones.len())
+        space = Task::space_with(&title, &description, &milestones, metadata.as_ref())
     )]
     pub task: Account<'info, Task>,
     
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub owner_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = CategoryHead::SIZE,
+        seeds = [b"category_head".as_ref(), &[category as u8]],
+        bump,
+        constraint = category_head.current_page == category_page @ AgoraError::StaleCategoryPage
+    )]
+    pub category_head: Account<'info, CategoryHead>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = CategoryIndex::SIZE,
+        seeds = [b"category".as_ref(), &[category as u8], &category_page.to_le_bytes()],
+        bump
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
+    
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+#[instruction(
+    description: Option<String>,
+    budget: Option<u64>,
+    deadline: Option<i64>,
+    metadata: Option<TaskMetadata>,
+    milestone_ops: Vec<MilestoneOp>
+)]
 pub struct UpdateTask<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -203,9 +1335,14 @@ pub struct UpdateTask<'info> {
     #[account(
         mut,
         constraint = task.owner == owner.key(),
-        constraint = task.status == TaskStatus::Open
+        constraint = task.status == TaskStatus::Open,
+        realloc = task.updated_space(description.as_ref(), metadata.as_ref(), &milestone_ops),
+        realloc::payer = owner,
+        realloc::zero = false
     )]
     pub task: Account<'info, Task>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -220,34 +1357,31 @@ pub struct CancelTask<'info> {
         constraint = task.escrow_account.is_none()
     )]
     pub task: Account<'info, Task>,
-}
-
-#[derive(Accounts)]
-#[instruction(proposal: String)]
-pub struct SubmitBid<'info> {
-    #[account(mut)]
-    pub bidder: Signer<'info>,
     
     #[account(
         mut,
-        constraint = task.status == TaskStatus::Open,
-        constraint = task.owner != bidder.key()
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
     )]
-    pub task: Account<'info, Task>,
+    pub owner_profile: Account<'info, AgentProfile>,
     
     #[account(
-        init,
-        payer = bidder,
-        space = Bid::space()
+        mut,
+        seeds = [b"category".as_ref(), &[task.category as u8], &task.category_page.to_le_bytes()],
+        bump = category_index.bump
     )]
-    pub bid: Account<'info, Bid>,
+    pub category_index: Account<'info, CategoryIndex>,
     
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"category_head".as_ref(), &[task.category as u8]],
+        bump = category_head.bump
+    )]
+    pub category_head: Account<'info, CategoryHead>,
 }
 
 #[derive(Accounts)]
-pub struct AcceptBid<'info> {
-    #[account(mut)]
+pub struct ManageInvitees<'info> {
     pub owner: Signer<'info>,
     
     #[account(
@@ -256,247 +1390,1273 @@ pub struct AcceptBid<'info> {
         constraint = task.status == TaskStatus::Open
     )]
     pub task: Account<'info, Task>,
-    
-    #[account(
-        mut,
-        constraint = bid.task == task.key(),
-        constraint = bid.status == BidStatus::Pending
-    )]
-    pub bid: Account<'info, Bid>,
 }
 
 #[derive(Accounts)]
-pub struct RejectBid<'info> {
-    #[account(mut)]
+pub struct SetReleaseApprovers<'info> {
     pub owner: Signer<'info>,
     
     #[account(
-        constraint = task.owner == owner.key()
+        mut,
+        constraint = task.owner == owner.key(),
+        constraint = task.status == TaskStatus::Open || task.status == TaskStatus::InProgress,
+        constraint = !task.approvers_locked() @ AgoraError::ApproversLocked
     )]
     pub task: Account<'info, Task>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeApproval<'info> {
+    pub approver: Signer<'info>,
     
     #[account(
         mut,
-        constraint = bid.task == task.key(),
-        constraint = bid.status == BidStatus::Pending
+        constraint = task.approver_index(&approver.key()).is_some() @ AgoraError::NotApprover
     )]
-    pub bid: Account<'info, Bid>,
+    pub task: Account<'info, Task>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawBid<'info> {
-    #[account(mut)]
-    pub bidder: Signer<'info>,
+pub struct TransferTaskOwnership<'info> {
+    pub owner: Signer<'info>,
     
     #[account(
         mut,
-        constraint = bid.bidder == bidder.key(),
-        constraint = bid.status == BidStatus::Pending,
-        close = bidder
+        constraint = task.owner == owner.key(),
+        constraint = task.status == TaskStatus::Open || task.status == TaskStatus::InProgress
     )]
-    pub bid: Account<'info, Bid>,
-    
-    pub system_program: Program<'info, System>,
+    pub task: Account<'info, Task>,
 }
 
 #[derive(Accounts)]
-pub struct FundEscrow<'info> {
-    #[account(mut)]
-    pub client: Signer<'info>,
+pub struct AcceptTaskOwnership<'info> {
+    pub new_owner: Signer<'info>,
     
     #[account(
         mut,
-        constraint = task.owner == client.key(),
-        constraint = task.status == TaskStatus::InProgress,
-        constraint = task.accepted_bid.is_some()
+        constraint = task.pending_owner == Some(new_owner.key()) @ AgoraError::NotPendingOwner,
+        constraint = task.status == TaskStatus::Open || task.status == TaskStatus::InProgress
     )]
     pub task: Account<'info, Task>,
     
-    pub accepted_bid: Account<'info, Bid>,
+    #[account(
+        mut,
+        seeds = [b"profile", task.owner.as_ref()],
+        bump
+    )]
+    pub old_owner_profile: Account<'info, AgentProfile>,
     
     #[account(
-        init,
-        payer = client,
-        space = Escrow::SIZE,
-        seeds = [b"escrow", task.key().as_ref()],
+        mut,
+        seeds = [b"profile", new_owner.key().as_ref()],
         bump
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub new_owner_profile: Account<'info, AgentProfile>,
     
+    /// Required once the task's escrow is funded.
     #[account(
         mut,
-        constraint = client_token_account.owner == client.key(),
-        constraint = client_token_account.mint == token_mint.key()
+        seeds = [b"escrow", task.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.freelancer != new_owner.key() @ AgoraError::Unauthorized
     )]
-    pub client_token_account: Account<'info, TokenAccount>,
+    pub escrow: Option<Account<'info, Escrow>>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireTask<'info> {
+    #[account(
+        mut,
+        constraint = task.status == TaskStatus::Open
+    )]
+    pub task: Account<'info, Task>,
     
     #[account(
-        init,
-        payer = client,
-        token::mint = token_mint,
-        token::authority = escrow,
-        seeds = [b"escrow_token", escrow.key().as_ref()],
+        mut,
+        seeds = [b"profile", task.owner.as_ref()],
         bump
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub owner_profile: Account<'info, AgentProfile>,
     
-    pub token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"category".as_ref(), &[task.category as u8], &task.category_page.to_le_bytes()],
+        bump = category_index.bump
+    )]
+    pub category_index: Account<'info, CategoryIndex>,
     
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    #[account(
+        mut,
+        seeds = [b"category_head".as_ref(), &[task.category as u8]],
+        bump = category_head.bump
+    )]
+    pub category_head: Account<'info, CategoryHead>,
 }
 
 #[derive(Accounts)]
-pub struct CompleteMilestone<'info> {
+#[instruction(proposal: String)]
+pub struct SubmitBid<'info> {
     #[account(mut)]
-    pub freelancer: Signer<'info>,
+    pub bidder: Signer<'info>,
     
     #[account(
         mut,
-        constraint = task.accepted_bid.is_some()
+        constraint = task.status == TaskStatus::Open,
+        constraint = task.owner != bidder.key()
     )]
     pub task: Account<'info, Task>,
     
     #[account(
-        constraint = bid.bidder == freelancer.key(),
-        constraint = bid.task == task.key()
+        init,
+        payer = bidder,
+        space = Bid::space()
     )]
     pub bid: Account<'info, Bid>,
+    
+    #[account(
+        init,
+        payer = bidder,
+        space = BidBond::SIZE,
+        seeds = [b"bid_bond", bid.key().as_ref()],
+        bump
+    )]
+    pub bid_bond: Option<Account<'info, BidBond>>,
+    
+    /// Present when `bidder` is a delegate bidding for the profile owner.
+    #[account(
+        constraint = delegation.delegate == bidder.key() @ AgoraError::Unauthorized
+    )]
+    pub delegation: Option<Account<'info, Delegation>>,
+    
+    #[account(
+        constraint = bidder_profile.owner == delegation
+            .as_ref()
+            .map_or(bidder.key(), |d| d.owner) @ AgoraError::Unauthorized
+    )]
+    pub bidder_profile: Option<Account<'info, AgentProfile>>,
+    
+    /// When present, the bid is checked against the agent's availability and
+    /// concurrency limit.
+    #[account(
+        constraint = bidder_manifest.owner == delegation
+            .as_ref()
+            .map_or(bidder.key(), |d| d.owner) @ AgoraError::Unauthorized
+    )]
+    pub bidder_manifest: Option<Account<'info, AgentManifest>>,
+    
+    #[account(
+        constraint = bidder_stake.owner == delegation
+            .as_ref()
+            .map_or(bidder.key(), |d| d.owner) @ AgoraError::StakeMismatch
+    )]
+    pub bidder_stake: Option<Account<'info, StakeVault>>,
+    
+    /// CHECK: The task owner's blocklist PDA, which may not exist yet
+    #[account(
+        seeds = [b"blocklist", task.owner.as_ref()],
+        bump
+    )]
+    pub owner_blocklist: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ReleasePayment<'info> {
+pub struct AcceptBid<'info> {
     #[account(mut)]
-    pub client: Signer<'info>,
+    pub owner: Signer<'info>,
     
     #[account(
         mut,
-        constraint = task.owner == client.key()
+        constraint = task.owner == owner.key(),
+        constraint = task.status == TaskStatus::Open
     )]
     pub task: Account<'info, Task>,
     
     #[account(
         mut,
-        seeds = [b"escrow", task.key().as_ref()],
-        bump = escrow.bump
+        constraint = bid.task == task.key(),
+        constraint = bid.status == BidStatus::Pending
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub bid: Account<'info, Bid>,
     
+    /// Required when the task has a minimum stake.
     #[account(
         mut,
-        constraint = escrow_token_account.owner == escrow.key(),
-        constraint = escrow_token_account.mint == escrow.token_mint
+        constraint = bidder_stake.owner == bid.bidder @ AgoraError::StakeMismatch
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub bidder_stake: Option<Account<'info, StakeVault>>,
     
     #[account(
         mut,
-        constraint = freelancer_token_account.mint == escrow.token_mint
+        seeds = [b"category".as_ref(), &[task.category as u8], &task.category_page.to_le_bytes()],
+        bump = category_index.bump
     )]
-    pub freelancer_token_account: Account<'info, TokenAccount>,
+    pub category_index: Account<'info, CategoryIndex>,
     
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        seeds = [b"category_head".as_ref(), &[task.category as u8]],
+        bump = category_head.bump
+    )]
+    pub category_head: Account<'info, CategoryHead>,
 }
 
 #[derive(Accounts)]
-pub struct RequestRefund<'info> {
-    #[account(mut)]
-    pub client: Signer<'info>,
+pub struct CounterOffer<'info> {
+    pub party: Signer<'info>,
+    
+    #[account(
+        constraint = task.status == TaskStatus::Open,
+        constraint = party.key() == task.owner || party.key() == bid.bidder @ AgoraError::NotBidParty
+    )]
+    pub task: Account<'info, Task>,
     
     #[account(
         mut,
-        constraint = task.owner == client.key(),
-        constraint = task.status != TaskStatus::Completed
+        constraint = bid.task == task.key(),
+        constraint = bid.status == BidStatus::Pending
+    )]
+    pub bid: Account<'info, Bid>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptCounter<'info> {
+    pub party: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = task.status == TaskStatus::Open,
+        constraint = party.key() == task.owner || party.key() == bid.bidder @ AgoraError::NotBidParty
     )]
     pub task: Account<'info, Task>,
     
     #[account(
         mut,
-        seeds = [b"escrow", task.key().as_ref()],
-        bump = escrow.bump
+        constraint = bid.task == task.key(),
+        constraint = bid.status == BidStatus::Pending
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub bid: Account<'info, Bid>,
     
+    /// Required when the task has a minimum stake.
     #[account(
         mut,
-        constraint = escrow_token_account.owner == escrow.key(),
-        constraint = escrow_token_account.mint == escrow.token_mint
+        constraint = bidder_stake.owner == bid.bidder @ AgoraError::StakeMismatch
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub bidder_stake: Option<Account<'info, StakeVault>>,
     
     #[account(
         mut,
-        constraint = client_token_account.owner == client.key(),
-        constraint = client_token_account.mint == escrow.token_mint
+        seeds = [b"category".as_ref(), &[task.category as u8], &task.category_page.to_le_bytes()],
+        bump = category_index.bump
     )]
-    pub client_token_account: Account<'info, TokenAccount>,
+    pub category_index: Account<'info, CategoryIndex>,
     
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        seeds = [b"category_head".as_ref(), &[task.category as u8]],
+        bump = category_head.bump
+    )]
+    pub category_head: Account<'info, CategoryHead>,
 }
 
 #[derive(Accounts)]
-pub struct InitializeAgentProfile<'info> {
+pub struct RejectBid<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     
     #[account(
-        init,
-        payer = owner,
-        space = AgentProfile::space(),
-        seeds = [b"profile", owner.key().as_ref()],
-        bump
+        constraint = task.owner == owner.key()
     )]
-    pub profile: Account<'info, AgentProfile>,
+    pub task: Account<'info, Task>,
     
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        constraint = bid.task == task.key(),
+        constraint = bid.status == BidStatus::Pending
+    )]
+    pub bid: Account<'info, Bid>,
 }
 
 #[derive(Accounts)]
-#[instruction(rating: u8, review_text: String)]
-pub struct SubmitReview<'info> {
+pub struct WithdrawBid<'info> {
     #[account(mut)]
-    pub reviewer: Signer<'info>,
+    pub bidder: Signer<'info>,
     
     #[account(
-        constraint = task.status == TaskStatus::Completed,
-        constraint = task.owner == reviewer.key() || bid.bidder == reviewer.key()
+        mut,
+        constraint = bid.bidder == bidder.key(),
+        constraint = bid.status == BidStatus::Pending,
+        close = bidder
     )]
-    pub task: Account<'info, Task>,
+    pub bid: Account<'info, Bid>,
     
     #[account(
-        constraint = bid.task == task.key()
+        mut,
+        seeds = [b"bid_bond", bid.key().as_ref()],
+        bump = bid_bond.bump,
+        close = bidder
     )]
-    pub bid: Account<'info, Bid>,
+    pub bid_bond: Option<Account<'info, BidBond>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExpiredBid<'info> {
+    pub task: Account<'info, Task>,
     
-    /// CHECK: Reviewee is the other party in the task
     #[account(
         mut,
-        constraint = reviewee.key() != reviewer.key()
+        constraint = bid.task == task.key(),
+        constraint = bid.status == BidStatus::Pending,
+        close = bidder
     )]
-    pub reviewee: AccountInfo<'info>,
+    pub bid: Account<'info, Bid>,
     
+    /// CHECK: Receives the bid's rent, must be the bidder
     #[account(
         mut,
-        seeds = [b"profile", reviewee.key().as_ref()],
-        bump
+        constraint = bidder.key() == bid.bidder
     )]
-    pub reviewee_profile: Account<'info, AgentProfile>,
+    pub bidder: AccountInfo<'info>,
     
     #[account(
-        init,
-        payer = reviewer,
-        space = Review::space()
+        mut,
+        seeds = [b"bid_bond", bid.key().as_ref()],
+        bump = bid_bond.bump,
+        close = bidder
     )]
-    pub review: Account<'info, Review>,
-    
-    pub system_program: Program<'info, System>,
+    pub bid_bond: Option<Account<'info, BidBond>>,
 }
 
-#[program]
-pub mod agora {
-    use super::*;
-
+#[derive(Accounts)]
+pub struct ReclaimBidBond<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        mut,
+        constraint = bid.bidder == bidder.key(),
+        constraint = bid.task == task.key()
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(
+        mut,
+        seeds = [b"bid_bond", bid.key().as_ref()],
+        bump = bid_bond.bump,
+        close = bidder
+    )]
+    pub bid_bond: Account<'info, BidBond>,
+}
+
+#[derive(Accounts)]
+pub struct ForfeitBidBond<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        constraint = task.owner == owner.key(),
+        constraint = task.accepted_bid == Some(bid.key())
+    )]
+    pub task: Account<'info, Task>,
+    
+    pub bid: Account<'info, Bid>,
+    
+    #[account(
+        mut,
+        seeds = [b"bid_bond", bid.key().as_ref()],
+        bump = bid_bond.bump,
+        close = owner
+    )]
+    pub bid_bond: Account<'info, BidBond>,
+}
+
+#[derive(Accounts)]
+pub struct FundEscrow<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = task.owner == client.key(),
+        constraint = task.status == TaskStatus::InProgress,
+        constraint = task.accepted_bid.is_some()
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        constraint = task.accepted_bid == Some(accepted_bid.key())
+    )]
+    pub accepted_bid: Account<'info, Bid>,
+    
+    #[account(
+        init,
+        payer = client,
+        space = Escrow::SIZE,
+        seeds = [b"escrow", task.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        mut,
+        constraint = client_token_account.owner == client.key(),
+        constraint = client_token_account.mint == token_mint.key()
+    )]
+    pub client_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = client,
+        token::mint = token_mint,
+        token::authority = escrow,
+        seeds = [b"escrow_token", escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    pub token_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", client.key().as_ref()],
+        bump
+    )]
+    pub client_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", accepted_bid.bidder.as_ref()],
+        bump
+    )]
+    pub freelancer_profile: Account<'info, AgentProfile>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteMilestone<'info> {
+    #[account(mut)]
+    pub freelancer: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = task.accepted_bid.is_some()
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        mut,
+        constraint = bid.bidder == delegation
+            .as_ref()
+            .map_or(freelancer.key(), |d| d.owner),
+        constraint = bid.task == task.key(),
+        constraint = task.accepted_bid == Some(bid.key())
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    /// Present when `freelancer` is a delegate acting for the bidder.
+    #[account(
+        constraint = delegation.delegate == freelancer.key() @ AgoraError::Unauthorized
+    )]
+    pub delegation: Option<Account<'info, Delegation>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveRelease<'info> {
+    pub approver: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = task.status == TaskStatus::InProgress,
+        constraint = task.approver_index(&approver.key()).is_some() @ AgoraError::NotApprover
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", task.key().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == escrow.key(),
+        constraint = escrow_token_account.mint == escrow.token_mint
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_token_account.owner == escrow.freelancer,
+        constraint = freelancer_token_account.mint == escrow.token_mint
+    )]
+    pub freelancer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", task.owner.as_ref()],
+        bump
+    )]
+    pub client_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", escrow.freelancer.as_ref()],
+        bump
+    )]
+    pub freelancer_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, MarketplaceConfig>,
+    
+    /// Required when the config sets a protocol fee.
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury,
+        constraint = treasury_token_account.mint == escrow.token_mint
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Required on the payment that completes a referred freelancer's first
+    /// task when it carries a referral reward.
+    /// CHECK: Must be the freelancer's referrer; may have been closed since
+    #[account(
+        constraint = Some(referrer_profile.key()) == freelancer_profile.referrer
+    )]
+    pub referrer_profile: Option<UncheckedAccount<'info>>,
+    
+    /// Required alongside a live referrer profile; owner checked in the handler.
+    #[account(
+        mut,
+        constraint = referrer_token_account.mint == escrow.token_mint
+    )]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestRefund<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = task.owner == client.key(),
+        constraint = task.status != TaskStatus::Completed,
+        constraint = task.status != TaskStatus::Disputed
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", task.key().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == escrow.key(),
+        constraint = escrow_token_account.mint == escrow.token_mint
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = client_token_account.owner == client.key(),
+        constraint = client_token_account.mint == escrow.token_mint
+    )]
+    pub client_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", client.key().as_ref()],
+        bump
+    )]
+    pub client_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", escrow.freelancer.as_ref()],
+        bump
+    )]
+    pub freelancer_profile: Account<'info, AgentProfile>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAgentProfile<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = AgentProfile::space(),
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    /// The referring agent's profile, if any.
+    pub referrer_profile: Option<Account<'info, AgentProfile>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, bio_uri: String, contact_uri: String)]
+pub struct UpdateAgentProfile<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump,
+        realloc = AgentProfile::space_with(bio_uri.len(), contact_uri.len(), &profile.skill_tags),
+        realloc::payer = owner,
+        realloc::zero = false,
+        constraint = profile.version == AgentProfile::VERSION @ AgoraError::ProfileNeedsMigration
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAgentProfile<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// CHECK: Read and rewritten by hand, since an older layout cannot be
+    /// loaded as `AgentProfile`; the seeds and owner check pin it to this
+    /// signer's profile.
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub profile: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAgentProfile<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump,
+        constraint = profile.version == AgentProfile::VERSION @ AgoraError::ProfileNeedsMigration,
+        constraint = !profile.has_open_work() @ AgoraError::ProfileHasOpenWork,
+        constraint = profile.skill_tags.is_empty() @ AgoraError::ProfileHasSkillTags,
+        close = owner
+    )]
+    pub profile: Account<'info, AgentProfile>,
+}
+
+#[derive(Accounts)]
+#[instruction(tag: String)]
+pub struct AddSkillTag<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump,
+        realloc = profile.current_space() + 4 + tag.len() + 4,
+        realloc::payer = owner,
+        realloc::zero = false,
+        constraint = profile.version == AgentProfile::VERSION @ AgoraError::ProfileNeedsMigration
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = SkillIndex::space(0),
+        seeds = [b"skill", SkillIndex::tag_hash(&tag).as_ref()],
+        bump
+    )]
+    pub skill_index: Account<'info, SkillIndex>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tag: String)]
+pub struct RemoveSkillTag<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"skill", SkillIndex::tag_hash(&tag).as_ref()],
+        bump = skill_index.bump
+    )]
+    pub skill_index: Account<'info, SkillIndex>,
+}
+
+#[derive(Accounts)]
+#[instruction(rating: u8, review_text: String)]
+pub struct SubmitReview<'info> {
+    #[account(mut)]
+    pub reviewer: Signer<'info>,
+    
+    #[account(
+        constraint = task.status == TaskStatus::Completed,
+        constraint = task.owner == reviewer.key() || bid.bidder == reviewer.key()
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        constraint = bid.task == task.key(),
+        constraint = task.accepted_bid == Some(bid.key())
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    /// CHECK: Reviewee is the other party in the task
+    #[account(
+        mut,
+        constraint = reviewee.key() == if reviewer.key() == task.owner {
+            bid.bidder
+        } else {
+            task.owner
+        } @ AgoraError::InvalidReviewee
+    )]
+    pub reviewee: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", reviewee.key().as_ref()],
+        bump
+    )]
+    pub reviewee_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = reviewer,
+        space = Review::space(),
+        seeds = [b"review", task.key().as_ref(), reviewer.key().as_ref()],
+        bump
+    )]
+    pub review: Account<'info, Review>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RespondToReview<'info> {
+    pub reviewee: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"review", review.task.as_ref(), review.reviewer.as_ref()],
+        bump,
+        constraint = review.reviewee == reviewee.key() @ AgoraError::Unauthorized
+    )]
+    pub review: Account<'info, Review>,
+}
+
+#[derive(Accounts)]
+pub struct FlagReview<'info> {
+    pub moderator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_moderator(&moderator.key()) @ AgoraError::Unauthorized
+    )]
+    pub config: Account<'info, MarketplaceConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"review", review.task.as_ref(), review.reviewer.as_ref()],
+        bump,
+        constraint = review.flagged_by.is_none() @ AgoraError::ReviewAlreadyFlagged
+    )]
+    pub review: Account<'info, Review>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", review.reviewee.as_ref()],
+        bump
+    )]
+    pub reviewee_profile: Account<'info, AgentProfile>,
+}
+
+#[derive(Accounts)]
+#[instruction(skill: String)]
+pub struct EndorseSkill<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    #[account(
+        constraint = task.owner == client.key(),
+        constraint = task.status == TaskStatus::Completed
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        constraint = task.accepted_bid == Some(bid.key())
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", bid.bidder.as_ref()],
+        bump
+    )]
+    pub freelancer_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = client,
+        space = Endorsement::SIZE,
+        seeds = [b"endorsement", task.key().as_ref(), SkillIndex::tag_hash(&skill).as_ref()],
+        bump
+    )]
+    pub endorsement: Account<'info, Endorsement>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetReputation<'info> {
+    pub profile: Account<'info, AgentProfile>,
+}
+
+#[derive(Accounts)]
+pub struct BlockBidder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Blocklist::SIZE,
+        seeds = [b"blocklist", owner.key().as_ref()],
+        bump
+    )]
+    pub blocklist: Account<'info, Blocklist>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblockBidder<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"blocklist", owner.key().as_ref()],
+        bump = blocklist.bump
+    )]
+    pub blocklist: Account<'info, Blocklist>,
+}
+
+#[derive(Accounts)]
+pub struct DepositStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakeVault::SIZE,
+        seeds = [b"stake", profile.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, StakeVault>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", stake_vault.profile.as_ref()],
+        bump = stake_vault.bump,
+        constraint = stake_vault.owner == owner.key() @ AgoraError::Unauthorized
+    )]
+    pub stake_vault: Account<'info, StakeVault>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseStakeLock<'info> {
+    #[account(
+        constraint = task.status == TaskStatus::Completed ||
+            task.status == TaskStatus::Cancelled ||
+            task.status == TaskStatus::Expired @ AgoraError::StakeLocked
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        mut,
+        constraint = bid.task == task.key(),
+        constraint = bid.stake_locked
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(
+        mut,
+        constraint = stake_vault.owner == bid.bidder @ AgoraError::StakeMismatch
+    )]
+    pub stake_vault: Account<'info, StakeVault>,
+}
+
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    #[account(mut)]
+    pub party: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = task.status == TaskStatus::InProgress,
+        constraint = task.escrow_account.is_some(),
+        constraint = party.key() == task.owner || delegation
+            .as_ref()
+            .map_or(party.key(), |d| d.owner) == bid.bidder @ AgoraError::NotBidParty
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        constraint = task.accepted_bid == Some(bid.key())
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    /// Present when `party` is a delegate acting for the bidder.
+    #[account(
+        constraint = delegation.delegate == party.key() @ AgoraError::Unauthorized
+    )]
+    pub delegation: Option<Account<'info, Delegation>>,
+    
+    #[account(
+        init,
+        payer = party,
+        space = Dispute::SIZE,
+        seeds = [b"dispute", task.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    pub arbiter: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_moderator(&arbiter.key()) @ AgoraError::Unauthorized
+    )]
+    pub config: Account<'info, MarketplaceConfig>,
+    
+    #[account(
+        mut,
+        constraint = task.status == TaskStatus::Disputed
+    )]
+    pub task: Account<'info, Task>,
+    
+    #[account(
+        mut,
+        constraint = task.accepted_bid == Some(bid.key())
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(
+        mut,
+        seeds = [b"dispute", task.key().as_ref()],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", task.key().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        mut,
+        constraint = escrow_token_account.owner == escrow.key(),
+        constraint = escrow_token_account.mint == escrow.token_mint
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = client_token_account.owner == escrow.client,
+        constraint = client_token_account.mint == escrow.token_mint
+    )]
+    pub client_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_token_account.owner == escrow.freelancer,
+        constraint = freelancer_token_account.mint == escrow.token_mint
+    )]
+    pub freelancer_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Receives slashed stake, must be the client
+    #[account(
+        mut,
+        constraint = client.key() == escrow.client
+    )]
+    pub client: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", escrow.client.as_ref()],
+        bump
+    )]
+    pub client_profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", escrow.freelancer.as_ref()],
+        bump
+    )]
+    pub freelancer_profile: Account<'info, AgentProfile>,
+    
+    /// Required when the accepted bid locked the freelancer's stake.
+    #[account(
+        mut,
+        seeds = [b"stake", freelancer_profile.key().as_ref()],
+        bump = freelancer_stake.bump
+    )]
+    pub freelancer_stake: Option<Account<'info, StakeVault>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAgentManifest<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = AgentManifest::SIZE,
+        seeds = [b"manifest", profile.key().as_ref()],
+        bump
+    )]
+    pub manifest: Account<'info, AgentManifest>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAgentManifest<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"manifest", manifest.profile.as_ref()],
+        bump = manifest.bump,
+        constraint = manifest.owner == owner.key() @ AgoraError::Unauthorized
+    )]
+    pub manifest: Account<'info, AgentManifest>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct CreateDelegation<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = Delegation::SIZE,
+        seeds = [b"delegation", profile.key().as_ref(), delegate.as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"delegation", delegation.profile.as_ref(), delegation.delegate.as_ref()],
+        bump = delegation.bump,
+        constraint = delegation.owner == owner.key() @ AgoraError::Unauthorized,
+        close = owner
+    )]
+    pub delegation: Account<'info, Delegation>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = MarketplaceConfig::SIZE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, MarketplaceConfig>,
+    
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+    )]
+    pub program: Program<'info, crate::program::Agora>,
+    
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ AgoraError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetArbiter<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgoraError::Unauthorized
+    )]
+    pub config: Account<'info, MarketplaceConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgoraError::Unauthorized
+    )]
+    pub config: Account<'info, MarketplaceConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u32)]
+pub struct CreateBadgeDefinition<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgoraError::Unauthorized
+    )]
+    pub config: Account<'info, MarketplaceConfig>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = BadgeDefinition::space(),
+        seeds = [b"badge_definition", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub definition: Account<'info, BadgeDefinition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBadge<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"badge_definition", definition.id.to_le_bytes().as_ref()],
+        bump = definition.bump
+    )]
+    pub definition: Account<'info, BadgeDefinition>,
+    
+    #[account(
+        seeds = [b"profile", profile.owner.as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, AgentProfile>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = Badge::SIZE,
+        seeds = [b"badge", definition.key().as_ref(), profile.key().as_ref()],
+        bump
+    )]
+    pub badge: Account<'info, Badge>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[program]
+pub mod agora {
+    use super::*;
+
+    #[allow(clippy::too_many_arguments)]
     pub fn post_task(
         ctx: Context<PostTask>,
         title: String,
@@ -504,32 +2664,92 @@ pub mod agora {
         budget: u64,
         milestones: Vec<Milestone>,
         deadline: i64,
+        requirements: Option<BidRequirements>,
+        category: TaskCategory,
+        category_page: u32,
+        metadata: Option<TaskMetadata>,
     ) -> Result<()> {
         let task = &mut ctx.accounts.task;
         
         require!(!title.is_empty(), AgoraError::EmptyTitle);
         require!(title.len() <= Task::MAX_TITLE_LEN, AgoraError::TitleTooLong);
         require!(description.len() <= Task::MAX_DESC_LEN, AgoraError::DescriptionTooLong);
-        require!(!milestones.is_empty(), AgoraError::NoMilestones);
-        require!(milestones.len() <= Task::MAX_MILESTONES, AgoraError::TooManyMilestones);
-        
-        let total: u64 = milestones.iter().map(|m| m.amount).sum();
-        require!(total == budget, AgoraError::MilestoneAmountMismatch);
+        if let Some(metadata) = &metadata {
+            require!(description.is_empty(), AgoraError::InlineDescriptionOnCompactTask);
+            require!(metadata.is_valid(), AgoraError::InvalidTaskMetadata);
+        }
+        Task::validate_schedule(&milestones, budget)?;
         
         let clock = Clock::get()?;
         require!(deadline > clock.unix_timestamp, AgoraError::InvalidDeadline);
         
+        let requirements = requirements.unwrap_or_default();
+        require!(
+            requirements.required_skills.len() <= BidRequirements::MAX_REQUIRED_SKILLS,
+            AgoraError::TooManyRequiredSkills
+        );
+        require!(
+            requirements
+                .required_skills
+                .iter()
+                .all(|s| AgentProfile::is_valid_skill_tag(s)),
+            AgoraError::InvalidSkillTag
+        );
+        
         task.owner = ctx.accounts.owner.key();
         task.title = title;
         task.description = description;
         task.budget = budget;
         task.milestones = milestones;
+        for milestone in task.milestones.iter_mut() {
+            milestone.approvals = 0;
+        }
         task.deadline = deadline;
         task.status = TaskStatus::Open;
         task.accepted_bid = None;
         task.escrow_account = None;
+        task.requirements = requirements;
+        task.invitees = Vec::new();
+        task.category = category;
+        task.category_page = category_page;
+        task.scope_version = 0;
+        task.pending_owner = None;
+        task.approvers = Vec::new();
+        task.approval_threshold = 0;
         task.created_at = clock.unix_timestamp;
         task.updated_at = clock.unix_timestamp;
+        task.metadata = metadata;
+        
+        let owner_profile = &mut ctx.accounts.owner_profile;
+        owner_profile.tasks_posted = owner_profile
+            .tasks_posted
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        owner_profile.open_tasks = owner_profile
+            .open_tasks
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        
+        let category_index = &mut ctx.accounts.category_index;
+        if category_index.tasks.is_empty() {
+            category_index.category = category;
+            category_index.page = category_page;
+            category_index.bump = ctx.bumps.category_index;
+        }
+        require!(
+            category_index.tasks.len() < CategoryIndex::MAX_TASKS,
+            AgoraError::CategoryIndexFull
+        );
+        category_index.tasks.push(task.key());
+        
+        let category_head = &mut ctx.accounts.category_head;
+        category_head.category = category;
+        category_head.bump = ctx.bumps.category_head;
+        let after_page = category_page.checked_add(1).ok_or(AgoraError::MathOverflow)?;
+        category_head.next_page = category_head.next_page.max(after_page);
+        if category_index.tasks.len() == CategoryIndex::MAX_TASKS {
+            category_head.advance()?;
+        }
         
         msg!("Task posted: {}", task.title);
         Ok(())
@@ -540,18 +2760,41 @@ pub mod agora {
         description: Option<String>,
         budget: Option<u64>,
         deadline: Option<i64>,
+        metadata: Option<TaskMetadata>,
+        milestone_ops: Vec<MilestoneOp>,
     ) -> Result<()> {
         let task = &mut ctx.accounts.task;
         let clock = Clock::get()?;
         
         if let Some(desc) = description {
+            require!(!task.is_compact(), AgoraError::InlineDescriptionOnCompactTask);
             require!(desc.len() <= Task::MAX_DESC_LEN, AgoraError::DescriptionTooLong);
             task.description = desc;
         }
         
+        // Compact tasks point at a new document, so the hash must be refreshed with it.
+        if let Some(metadata) = metadata {
+            require!(task.is_compact(), AgoraError::TaskNotCompact);
+            require!(metadata.is_valid(), AgoraError::InvalidTaskMetadata);
+            task.metadata = Some(metadata);
+        }
+        
+        let scope_changed = budget.is_some() || !milestone_ops.is_empty();
         if let Some(bud) = budget {
             task.budget = bud;
         }
+        if !milestone_ops.is_empty() {
+            task.milestones = Task::apply_milestone_ops(&task.milestones, &milestone_ops)?;
+        }
+        Task::validate_schedule(&task.milestones, task.budget)?;
+        
+        // Pending bids priced the old scope; they can no longer be accepted.
+        if scope_changed {
+            task.scope_version = task
+                .scope_version
+                .checked_add(1)
+                .ok_or(AgoraError::MathOverflow)?;
+        }
         
         if let Some(dl) = deadline {
             require!(dl > clock.unix_timestamp, AgoraError::InvalidDeadline);
@@ -569,15 +2812,128 @@ pub mod agora {
         task.status = TaskStatus::Cancelled;
         task.updated_at = clock.unix_timestamp;
         
+        let owner_profile = &mut ctx.accounts.owner_profile;
+        owner_profile.open_tasks = owner_profile.open_tasks.saturating_sub(1);
+        
+        ctx.accounts
+            .category_index
+            .remove(&task.key(), &mut ctx.accounts.category_head);
+        
         msg!("Task cancelled: {}", task.title);
         Ok(())
     }
 
+    pub fn add_invitee(ctx: Context<ManageInvitees>, invitee: Pubkey) -> Result<()> {
+        let task = &mut ctx.accounts.task;
+        
+        require!(!task.invitees.contains(&invitee), AgoraError::AlreadyInvited);
+        require!(task.invitees.len() < Task::MAX_INVITEES, AgoraError::TooManyInvitees);
+        
+        task.invitees.push(invitee);
+        task.updated_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Invitee added: {:?}", invitee);
+        Ok(())
+    }
+
+    pub fn remove_invitee(ctx: Context<ManageInvitees>, invitee: Pubkey) -> Result<()> {
+        let task = &mut ctx.accounts.task;
+        
+        let index = task
+            .invitees
+            .iter()
+            .position(|k| *k == invitee)
+            .ok_or(AgoraError::InviteeNotFound)?;
+        task.invitees.remove(index);
+        task.updated_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Invitee removed: {:?}", invitee);
+        Ok(())
+    }
+
+    pub fn expire_task(ctx: Context<ExpireTask>) -> Result<()> {
+        let task = &mut ctx.accounts.task;
+        let clock = Clock::get()?;
+        
+        require!(clock.unix_timestamp > task.deadline, AgoraError::TaskNotExpired);
+        
+        task.status = TaskStatus::Expired;
+        task.updated_at = clock.unix_timestamp;
+        
+        let owner_profile = &mut ctx.accounts.owner_profile;
+        owner_profile.open_tasks = owner_profile.open_tasks.saturating_sub(1);
+        
+        ctx.accounts
+            .category_index
+            .remove(&task.key(), &mut ctx.accounts.category_head);
+        
+        msg!("Task expired: {}", task.title);
+        Ok(())
+    }
+
+    /// Proposes `new_owner` for the task. Proposing the current owner
+    /// withdraws a pending proposal.
+    pub fn transfer_task_ownership(
+        ctx: Context<TransferTaskOwnership>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        let task = &mut ctx.accounts.task;
+        
+        task.pending_owner = if new_owner == task.owner {
+            None
+        } else {
+            Some(new_owner)
+        };
+        task.updated_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Task ownership transfer proposed to {:?}", new_owner);
+        Ok(())
+    }
+
+    pub fn accept_task_ownership(ctx: Context<AcceptTaskOwnership>) -> Result<()> {
+        let task = &mut ctx.accounts.task;
+        let new_owner = ctx.accounts.new_owner.key();
+        
+        require!(
+            ctx.accounts.escrow.is_some() == task.escrow_account.is_some(),
+            AgoraError::MissingEscrowAccount
+        );
+        
+        let old_owner_profile = &mut ctx.accounts.old_owner_profile;
+        let new_owner_profile = &mut ctx.accounts.new_owner_profile;
+        old_owner_profile.open_tasks = old_owner_profile.open_tasks.saturating_sub(1);
+        new_owner_profile.open_tasks = new_owner_profile
+            .open_tasks
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        
+        // Refunds and releases follow the escrow's client.
+        if let Some(escrow) = ctx.accounts.escrow.as_mut() {
+            escrow.client = new_owner;
+            old_owner_profile.active_escrows = old_owner_profile.active_escrows.saturating_sub(1);
+            new_owner_profile.active_escrows = new_owner_profile
+                .active_escrows
+                .checked_add(1)
+                .ok_or(AgoraError::MathOverflow)?;
+        }
+        
+        // Designated approvers stay in place, so a transfer can't be used to
+        // drop the release threshold.
+        let previous_owner = task.owner;
+        task.owner = new_owner;
+        task.pending_owner = None;
+        task.updated_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Task ownership moved from {:?} to {:?}", previous_owner, new_owner);
+        Ok(())
+    }
+
     pub fn submit_bid(
         ctx: Context<SubmitBid>,
         amount: u64,
         timeline: i64,
         proposal: String,
+        milestone_amounts: Vec<u64>,
     ) -> Result<()> {
         require!(proposal.len() <= Bid::MAX_PROPOSAL_LEN, AgoraError::ProposalTooLong);
         require!(amount > 0, AgoraError::InvalidAmount);
@@ -587,14 +2943,142 @@ pub mod agora {
         let task = &ctx.accounts.task;
         require!(clock.unix_timestamp + timeline <= task.deadline, AgoraError::TimelineExceedsDeadline);
         
+        // A delegate bids on the owner's behalf; the bid and any payout belong
+        // to the owner.
+        let bidder = match &ctx.accounts.delegation {
+            Some(delegation) => {
+                delegation.authorize(Delegation::SUBMIT_BID, clock.unix_timestamp)?;
+                require!(amount <= delegation.max_bid_amount, AgoraError::DelegationCapExceeded);
+                delegation.owner
+            }
+            None => ctx.accounts.bidder.key(),
+        };
+        require!(task.owner != bidder, AgoraError::Unauthorized);
+        
+        if task.requirements.visibility == TaskVisibility::InviteOnly {
+            require!(
+                task.invitees.contains(&bidder),
+                AgoraError::NotInvited
+            );
+        }
+        
+        // Required so a blocked bidder can't skip the check; an owner who
+        // never blocked anyone has no account here.
+        let owner_blocklist = &ctx.accounts.owner_blocklist;
+        if owner_blocklist.owner == &crate::ID {
+            let blocklist = Blocklist::try_deserialize(&mut &owner_blocklist.try_borrow_data()?[..])?;
+            require!(!blocklist.blocked.contains(&bidder), AgoraError::BidderBlocked);
+        }
+        
+        if task.requirements.has_reputation_gate() {
+            let profile = ctx
+                .accounts
+                .bidder_profile
+                .as_ref()
+                .ok_or(AgoraError::InsufficientReputation)?;
+            require!(
+                profile.meets_reputation(&task.requirements),
+                AgoraError::InsufficientReputation
+            );
+        }
+        
+        if !task.requirements.required_skills.is_empty() {
+            let profile = ctx
+                .accounts
+                .bidder_profile
+                .as_ref()
+                .ok_or(AgoraError::MissingRequiredSkill)?;
+            require!(
+                profile.has_skills(&task.requirements.required_skills),
+                AgoraError::MissingRequiredSkill
+            );
+            require!(
+                profile.has_endorsements(
+                    &task.requirements.required_skills,
+                    task.requirements.min_skill_endorsements
+                ),
+                AgoraError::InsufficientEndorsements
+            );
+        }
+        
+        if task.requirements.min_stake > 0 {
+            let stake = ctx
+                .accounts
+                .bidder_stake
+                .as_ref()
+                .ok_or(AgoraError::InsufficientStake)?;
+            require!(
+                stake.available() >= task.requirements.min_stake,
+                AgoraError::InsufficientStake
+            );
+        }
+        
+        if let Some(manifest) = &ctx.accounts.bidder_manifest {
+            require!(manifest.available, AgoraError::AgentUnavailable);
+            let profile = ctx
+                .accounts
+                .bidder_profile
+                .as_ref()
+                .ok_or(AgoraError::ConcurrencyLimitReached)?;
+            require!(
+                manifest.has_capacity(profile.active_contracts),
+                AgoraError::ConcurrencyLimitReached
+            );
+        }
+        
+        // An empty schedule means the bidder accepts the task's milestone split
+        // scaled to their bid amount.
+        let milestone_amounts = if milestone_amounts.is_empty() {
+            task.scaled_milestone_amounts(amount)?
+        } else {
+            require!(
+                milestone_amounts.len() == task.milestones.len(),
+                AgoraError::BidMilestoneMismatch
+            );
+            let total = milestone_amounts
+                .iter()
+                .try_fold(0u64, |acc, a| acc.checked_add(*a))
+                .ok_or(AgoraError::MathOverflow)?;
+            require!(total == amount, AgoraError::MilestoneAmountMismatch);
+            milestone_amounts
+        };
+        
         let bid = &mut ctx.accounts.bid;
         bid.task = ctx.accounts.task.key();
-        bid.bidder = ctx.accounts.bidder.key();
+        bid.bidder = bidder;
         bid.amount = amount;
         bid.timeline = timeline;
         bid.proposal = proposal;
+        bid.milestone_amounts = milestone_amounts;
+        bid.bond = task.requirements.bid_bond;
         bid.status = BidStatus::Pending;
         bid.created_at = clock.unix_timestamp;
+        bid.accepted_at = 0;
+        bid.expires_at = task.deadline;
+        bid.stake_locked = false;
+        bid.scope_version = task.scope_version;
+        bid.delivered_at = 0;
+        
+        require!(
+            ctx.accounts.bid_bond.is_some() == (bid.bond > 0),
+            AgoraError::BidBondMismatch
+        );
+        
+        if let Some(bid_bond) = ctx.accounts.bid_bond.as_mut() {
+            bid_bond.bid = bid.key();
+            bid_bond.bidder = bid.bidder;
+            bid_bond.amount = bid.bond;
+            bid_bond.bump = ctx.bumps.bid_bond.unwrap();
+            
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.bidder.to_account_info(),
+                    to: bid_bond.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_ctx, bid.bond)?;
+        }
         
         msg!("Bid submitted for task: {:?}", bid.task);
         Ok(())
@@ -605,15 +3089,72 @@ pub mod agora {
         let bid = &mut ctx.accounts.bid;
         let clock = Clock::get()?;
         
-        bid.status = BidStatus::Accepted;
-        task.accepted_bid = Some(bid.key());
-        task.status = TaskStatus::InProgress;
-        task.updated_at = clock.unix_timestamp;
+        task.accept(
+            bid.key(),
+            bid,
+            ctx.accounts.bidder_stake.as_deref_mut(),
+            clock.unix_timestamp,
+        )?;
+        ctx.accounts
+            .category_index
+            .remove(&task.key(), &mut ctx.accounts.category_head);
         
         msg!("Bid accepted for task: {}", task.title);
         Ok(())
     }
 
+    pub fn counter_offer(ctx: Context<CounterOffer>, amount: u64, timeline: i64) -> Result<()> {
+        require!(amount > 0, AgoraError::InvalidAmount);
+        require!(timeline > 0, AgoraError::InvalidTimeline);
+        
+        let clock = Clock::get()?;
+        let task = &ctx.accounts.task;
+        let bid = &mut ctx.accounts.bid;
+        require!(clock.unix_timestamp <= bid.expires_at, AgoraError::BidExpired);
+        require!(clock.unix_timestamp + timeline <= task.deadline, AgoraError::TimelineExceedsDeadline);
+        require!(bid.offers.len() < Bid::MAX_OFFERS, AgoraError::TooManyOffers);
+        
+        bid.offers.push(Offer {
+            amount,
+            timeline,
+            proposed_by: ctx.accounts.party.key(),
+            created_at: clock.unix_timestamp,
+        });
+        
+        msg!("Counter-offer on bid {:?}: {} over {}s", bid.key(), amount, timeline);
+        Ok(())
+    }
+
+    pub fn accept_counter(ctx: Context<AcceptCounter>) -> Result<()> {
+        let task = &mut ctx.accounts.task;
+        let bid = &mut ctx.accounts.bid;
+        let clock = Clock::get()?;
+        
+        let offer = bid.offers.last().cloned().ok_or(AgoraError::NoCounterOffer)?;
+        require!(
+            offer.proposed_by != ctx.accounts.party.key(),
+            AgoraError::CannotAcceptOwnOffer
+        );
+        
+        // Keep the bidder's milestone split, scaled to the agreed amount.
+        bid.milestone_amounts = scale_amounts(&bid.milestone_amounts, bid.amount, offer.amount)?;
+        bid.amount = offer.amount;
+        bid.timeline = offer.timeline;
+        
+        task.accept(
+            bid.key(),
+            bid,
+            ctx.accounts.bidder_stake.as_deref_mut(),
+            clock.unix_timestamp,
+        )?;
+        ctx.accounts
+            .category_index
+            .remove(&task.key(), &mut ctx.accounts.category_head);
+        
+        msg!("Counter-offer accepted for task: {}", task.title);
+        Ok(())
+    }
+
     pub fn reject_bid(ctx: Context<RejectBid>) -> Result<()> {
         let bid = &mut ctx.accounts.bid;
         bid.status = BidStatus::Rejected;
@@ -622,10 +3163,75 @@ pub mod agora {
 
     pub fn withdraw_bid(ctx: Context<WithdrawBid>) -> Result<()> {
         let bid = &mut ctx.accounts.bid;
+        require!(
+            ctx.accounts.bid_bond.is_some() == (bid.bond > 0),
+            AgoraError::BidBondMismatch
+        );
         bid.status = BidStatus::Withdrawn;
         Ok(())
     }
 
+    pub fn close_expired_bid(ctx: Context<CloseExpiredBid>) -> Result<()> {
+        let task = &ctx.accounts.task;
+        let bid = &ctx.accounts.bid;
+        let clock = Clock::get()?;
+        
+        require!(
+            task.status == TaskStatus::Expired || clock.unix_timestamp > bid.expires_at,
+            AgoraError::BidNotExpired
+        );
+        require!(
+            ctx.accounts.bid_bond.is_some() == (bid.bond > 0),
+            AgoraError::BidBondMismatch
+        );
+        
+        msg!("Expired bid closed for task: {:?}", bid.task);
+        Ok(())
+    }
+
+    pub fn reclaim_bid_bond(ctx: Context<ReclaimBidBond>) -> Result<()> {
+        let task = &ctx.accounts.task;
+        let bid = &mut ctx.accounts.bid;
+        
+        let won = task.accepted_bid == Some(bid.key());
+        let lost = task.accepted_bid.is_some() && !won;
+        let delivered = won && bid.delivered_on_time();
+        // Refunded and client-won disputed tasks end up Cancelled too; a winner
+        // who never delivered stays forfeitable there.
+        let closed = (task.status == TaskStatus::Cancelled || task.status == TaskStatus::Expired) &&
+            (!won || delivered);
+        
+        require!(
+            bid.status == BidStatus::Rejected ||
+            closed ||
+            lost ||
+            delivered,
+            AgoraError::BidBondLocked
+        );
+        
+        // The bond account closes here, so the bid can still be withdrawn or
+        // closed without one.
+        bid.bond = 0;
+        
+        msg!("Bid bond returned: {}", ctx.accounts.bid_bond.amount);
+        Ok(())
+    }
+
+    pub fn forfeit_bid_bond(ctx: Context<ForfeitBidBond>) -> Result<()> {
+        let bid = &ctx.accounts.bid;
+        let clock = Clock::get()?;
+        
+        // A late first delivery doesn't save the bond.
+        require!(
+            !bid.delivered_on_time() &&
+            clock.unix_timestamp > bid.accepted_at + bid.timeline,
+            AgoraError::BidBondNotForfeitable
+        );
+        
+        msg!("Bid bond forfeited: {}", ctx.accounts.bid_bond.amount);
+        Ok(())
+    }
+
     pub fn fund_escrow(ctx: Context<FundEscrow>) -> Result<()> {
         let accepted_bid = &ctx.accounts.accepted_bid;
         
@@ -651,11 +3257,29 @@ pub mod agora {
         escrow.token_mint = ctx.accounts.token_mint.key();
         escrow.bump = ctx.bumps.escrow;
         
+        ctx.accounts.task.escrow_account = Some(escrow.key());
+        
+        for profile in [&mut ctx.accounts.client_profile, &mut ctx.accounts.freelancer_profile] {
+            profile.active_escrows = profile
+                .active_escrows
+                .checked_add(1)
+                .ok_or(AgoraError::MathOverflow)?;
+        }
+        let freelancer_profile = &mut ctx.accounts.freelancer_profile;
+        freelancer_profile.active_contracts = freelancer_profile
+            .active_contracts
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        
         msg!("Escrow funded with {} tokens", accepted_bid.amount);
         Ok(())
     }
 
     pub fn complete_milestone(ctx: Context<CompleteMilestone>, milestone_index: u8) -> Result<()> {
+        if let Some(delegation) = &ctx.accounts.delegation {
+            delegation.authorize(Delegation::COMPLETE_MILESTONE, Clock::get()?.unix_timestamp)?;
+        }
+        
         let task = &mut ctx.accounts.task;
         let index = milestone_index as usize;
         
@@ -666,20 +3290,67 @@ pub mod agora {
         
         milestone.completed = true;
         
+        let bid = &mut ctx.accounts.bid;
+        if bid.delivered_at == 0 {
+            bid.delivered_at = Clock::get()?.unix_timestamp;
+        }
+        
         msg!("Milestone {} marked as completed", milestone_index);
         Ok(())
     }
 
-    pub fn release_payment(ctx: Context<ReleasePayment>, milestone_index: u8) -> Result<()> {
+    pub fn set_release_approvers(
+        ctx: Context<SetReleaseApprovers>,
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(approvers.len() <= Task::MAX_APPROVERS, AgoraError::TooManyApprovers);
+        for (i, approver) in approvers.iter().enumerate() {
+            require!(!approvers[..i].contains(approver), AgoraError::DuplicateApprover);
+        }
+        // An empty set hands approval back to the owner alone.
+        require!(
+            approvers.is_empty() || (threshold >= 1 && threshold as usize <= approvers.len()),
+            AgoraError::InvalidApprovalThreshold
+        );
+        
+        let task = &mut ctx.accounts.task;
+        task.set_approvers(approvers, threshold);
+        task.updated_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Release approvers set: {} of {}", task.approval_threshold, task.approvers.len());
+        Ok(())
+    }
+
+    /// Records the signer's approval of a completed milestone, and pays it
+    /// out once the task's approval threshold is reached.
+    pub fn approve_release(ctx: Context<ApproveRelease>, milestone_index: u8) -> Result<()> {
         let task = &mut ctx.accounts.task;
         let escrow = &mut ctx.accounts.escrow;
         let index = milestone_index as usize;
         
         require!(index < task.milestones.len(), AgoraError::InvalidMilestoneIndex);
         
+        let approver_bit = 1u8 << task
+            .approver_index(&ctx.accounts.approver.key())
+            .ok_or(AgoraError::NotApprover)?;
+        let required_approvals = task.required_approvals();
+        
         let milestone = &mut task.milestones[index];
         require!(milestone.completed, AgoraError::MilestoneNotCompleted);
         require!(!milestone.paid, AgoraError::MilestoneAlreadyPaid);
+        require!(milestone.approvals & approver_bit == 0, AgoraError::AlreadyApproved);
+        
+        milestone.approvals |= approver_bit;
+        if milestone.approvals.count_ones() < required_approvals {
+            msg!(
+                "Release of milestone {} approved ({}/{})",
+                milestone_index,
+                milestone.approvals.count_ones(),
+                required_approvals
+            );
+            return Ok(());
+        }
         
         let amount = milestone.amount;
         milestone.paid = true;
@@ -689,33 +3360,141 @@ pub mod agora {
             task.status = TaskStatus::Completed;
         }
         
-        let escrow_key = escrow.key();
-        let seeds = &[b"escrow", escrow_key.as_ref(), &[escrow.bump]];
-        let signer = &[&seeds[..]];
+        let config = &ctx.accounts.config;
+        let fee = config.protocol_fee(amount);
         
-        let transfer_instruction = Transfer {
-            from: ctx.accounts.escrow_token_account.to_account_info(),
-            to: ctx.accounts.freelancer_token_account.to_account_info(),
-            authority: escrow.to_account_info(),
+        // The referral is settled once, on the payment that completes the
+        // referred freelancer's first task, whether or not it earns a reward.
+        let freelancer_profile = &mut ctx.accounts.freelancer_profile;
+        let settle_referral = all_paid
+            && freelancer_profile.referrer.is_some()
+            && !freelancer_profile.referral_paid;
+        if settle_referral {
+            freelancer_profile.referral_paid = true;
+        }
+        let referral = if settle_referral { config.referral_reward(fee) } else { 0 };
+        let referral = if referral > 0 {
+            let referrer_profile = ctx
+                .accounts
+                .referrer_profile
+                .as_ref()
+                .ok_or(AgoraError::MissingReferrerAccount)?;
+            if referrer_profile.owner != &crate::ID {
+                // The referrer closed their profile; the share stays with the treasury.
+                0
+            } else {
+                let referrer = AgentProfile::try_deserialize(
+                    &mut &referrer_profile.try_borrow_data()?[..],
+                )?;
+                let referrer_token_account = ctx
+                    .accounts
+                    .referrer_token_account
+                    .as_ref()
+                    .ok_or(AgoraError::MissingReferrerAccount)?;
+                require_keys_eq!(
+                    referrer_token_account.owner,
+                    referrer.owner,
+                    AgoraError::InvalidReferrerTokenAccount
+                );
+                referral
+            }
+        } else {
+            0
         };
         
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            transfer_instruction,
-            signer,
-        );
+        let task_key = task.key();
+        let seeds = &[b"escrow", task_key.as_ref(), &[escrow.bump]];
+        let signer = &[&seeds[..]];
+        
+        let payouts = [
+            (Some(ctx.accounts.freelancer_token_account.to_account_info()), amount - fee),
+            (
+                ctx.accounts.treasury_token_account.as_ref().map(|a| a.to_account_info()),
+                fee - referral,
+            ),
+            (
+                ctx.accounts.referrer_token_account.as_ref().map(|a| a.to_account_info()),
+                referral,
+            ),
+        ];
+        
+        for (recipient, payout) in payouts {
+            if payout == 0 {
+                continue;
+            }
+            let recipient = recipient.ok_or(AgoraError::MissingTreasuryAccount)?;
+            
+            let transfer_instruction = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: recipient,
+                authority: escrow.to_account_info(),
+            };
+            
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+                signer,
+            );
+            
+            token::transfer(cpi_ctx, payout)?;
+        }
+        
+        escrow.released_amount = escrow
+            .released_amount
+            .checked_add(amount)
+            .ok_or(AgoraError::MathOverflow)?;
+        
+        let client_profile = &mut ctx.accounts.client_profile;
+        client_profile.total_spent = client_profile
+            .total_spent
+            .checked_add(amount)
+            .ok_or(AgoraError::MathOverflow)?;
+        if all_paid {
+            client_profile.open_tasks = client_profile.open_tasks.saturating_sub(1);
+            client_profile.active_escrows = client_profile.active_escrows.saturating_sub(1);
+        }
+        
+        let freelancer_profile = &mut ctx.accounts.freelancer_profile;
+        freelancer_profile.total_earned = freelancer_profile
+            .total_earned
+            .checked_add(amount - fee)
+            .ok_or(AgoraError::MathOverflow)?;
+        if all_paid {
+            freelancer_profile.tasks_completed = freelancer_profile
+                .tasks_completed
+                .checked_add(1)
+                .ok_or(AgoraError::MathOverflow)?;
+            freelancer_profile.active_escrows = freelancer_profile.active_escrows.saturating_sub(1);
+            freelancer_profile.active_contracts = freelancer_profile.active_contracts.saturating_sub(1);
+        }
+        
+        msg!("Payment released for milestone {}: {} (fee {})", milestone_index, amount, fee);
+        Ok(())
+    }
+
+    pub fn revoke_approval(ctx: Context<RevokeApproval>, milestone_index: u8) -> Result<()> {
+        let task = &mut ctx.accounts.task;
+        let index = milestone_index as usize;
+        
+        require!(index < task.milestones.len(), AgoraError::InvalidMilestoneIndex);
         
-        token::transfer(cpi_ctx, amount)?;
+        let approver_bit = 1u8 << task
+            .approver_index(&ctx.accounts.approver.key())
+            .ok_or(AgoraError::NotApprover)?;
         
-        escrow.released_amount = escrow.released_amount.checked_add(amount).unwrap();
+        let milestone = &mut task.milestones[index];
+        require!(!milestone.paid, AgoraError::MilestoneAlreadyPaid);
+        require!(milestone.approvals & approver_bit != 0, AgoraError::ApprovalNotFound);
         
-        msg!("Payment released for milestone {}: {}", milestone_index, amount);
+        milestone.approvals &= !approver_bit;
+        
+        msg!("Approval of milestone {} revoked", milestone_index);
         Ok(())
     }
 
     pub fn request_refund(ctx: Context<RequestRefund>) -> Result<()> {
-        let task = &ctx.accounts.task;
-        let escrow = &ctx.accounts.escrow;
+        let task = &mut ctx.accounts.task;
+        let escrow = &mut ctx.accounts.escrow;
         let clock = Clock::get()?;
         
         require!(
@@ -727,8 +3506,8 @@ pub mod agora {
         let refund_amount = escrow.total_amount - escrow.released_amount;
         require!(refund_amount > 0, AgoraError::NoFundsToRefund);
         
-        let escrow_key = escrow.key();
-        let seeds = &[b"escrow", escrow_key.as_ref(), &[escrow.bump]];
+        let task_key = task.key();
+        let seeds = &[b"escrow", task_key.as_ref(), &[escrow.bump]];
         let signer = &[&seeds[..]];
         
         let transfer_instruction = Transfer {
@@ -745,6 +3524,18 @@ pub mod agora {
         
         token::transfer(cpi_ctx, refund_amount)?;
         
+        // The escrow now only accounts for what was actually paid out.
+        escrow.total_amount = escrow.released_amount;
+        task.status = TaskStatus::Cancelled;
+        task.updated_at = clock.unix_timestamp;
+        
+        let client_profile = &mut ctx.accounts.client_profile;
+        client_profile.open_tasks = client_profile.open_tasks.saturating_sub(1);
+        client_profile.active_escrows = client_profile.active_escrows.saturating_sub(1);
+        let freelancer_profile = &mut ctx.accounts.freelancer_profile;
+        freelancer_profile.active_escrows = freelancer_profile.active_escrows.saturating_sub(1);
+        freelancer_profile.active_contracts = freelancer_profile.active_contracts.saturating_sub(1);
+        
         msg!("Refund issued: {}", refund_amount);
         Ok(())
     }
@@ -755,43 +3546,695 @@ pub mod agora {
         let clock = Clock::get()?;
         let profile = &mut ctx.accounts.profile;
         
+        profile.version = AgentProfile::VERSION;
         profile.owner = ctx.accounts.owner.key();
         profile.name = name;
+        profile.bio_uri = String::new();
+        profile.contact_uri = String::new();
+        profile.skill_tags = Vec::new();
+        profile.skill_endorsements = Vec::new();
+        profile.endorsement_count = 0;
         profile.tasks_posted = 0;
         profile.tasks_completed = 0;
         profile.total_earned = 0;
         profile.total_spent = 0;
         profile.rating_sum = 0;
         profile.rating_count = 0;
+        profile.weighted_rating_sum = 0;
+        profile.weighted_amount = 0;
+        profile.reputation_updated_at = clock.unix_timestamp;
+        profile.open_tasks = 0;
+        profile.active_escrows = 0;
+        profile.active_contracts = 0;
+        profile.disputes_lost = 0;
+        profile.referrer = ctx.accounts.referrer_profile.as_ref().map(|p| p.key());
+        profile.referral_paid = false;
         profile.created_at = clock.unix_timestamp;
         
         msg!("Agent profile created: {}", profile.name);
         Ok(())
     }
 
+    pub fn update_agent_profile(
+        ctx: Context<UpdateAgentProfile>,
+        name: String,
+        bio_uri: String,
+        contact_uri: String,
+    ) -> Result<()> {
+        require!(name.len() <= AgentProfile::MAX_NAME_LEN, AgoraError::NameTooLong);
+        require!(bio_uri.len() <= AgentProfile::MAX_URI_LEN, AgoraError::UriTooLong);
+        require!(contact_uri.len() <= AgentProfile::MAX_URI_LEN, AgoraError::UriTooLong);
+        
+        let profile = &mut ctx.accounts.profile;
+        profile.name = name;
+        profile.bio_uri = bio_uri;
+        profile.contact_uri = contact_uri;
+        
+        msg!("Agent profile updated: {}", profile.name);
+        Ok(())
+    }
+
+    pub fn add_skill_tag(ctx: Context<AddSkillTag>, tag: String) -> Result<()> {
+        require!(AgentProfile::is_valid_skill_tag(&tag), AgoraError::InvalidSkillTag);
+        
+        let profile = &mut ctx.accounts.profile;
+        require!(!profile.skill_tags.contains(&tag), AgoraError::DuplicateSkillTag);
+        require!(
+            profile.skill_tags.len() < AgentProfile::MAX_SKILL_TAGS,
+            AgoraError::TooManySkillTags
+        );
+        profile.skill_tags.push(tag.clone());
+        profile.skill_endorsements.push(0);
+        
+        let skill_index = &mut ctx.accounts.skill_index;
+        if skill_index.tag.is_empty() {
+            skill_index.tag = tag.clone();
+            skill_index.bump = ctx.bumps.skill_index;
+        }
+        // A full index only stops listing new profiles; the tag itself still
+        // counts towards `required_skills`.
+        if skill_index.profiles.len() >= SkillIndex::MAX_PROFILES {
+            msg!("Skill tag added without indexing: {}", tag);
+            return Ok(());
+        }
+        skill_index.profiles.push(profile.key());
+        
+        // Grow the index by one entry, topping up rent from the owner.
+        let index_info = skill_index.to_account_info();
+        let new_len = SkillIndex::space(skill_index.profiles.len());
+        if new_len > index_info.data_len() {
+            let rent = Rent::get()?.minimum_balance(new_len);
+            let top_up = rent.saturating_sub(index_info.lamports());
+            if top_up > 0 {
+                let cpi_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: index_info.clone(),
+                    },
+                );
+                system_program::transfer(cpi_ctx, top_up)?;
+            }
+            index_info.resize(new_len)?;
+        }
+        
+        msg!("Skill tag added: {}", tag);
+        Ok(())
+    }
+
+    pub fn remove_skill_tag(ctx: Context<RemoveSkillTag>, tag: String) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        let index = profile
+            .skill_tags
+            .iter()
+            .position(|t| *t == tag)
+            .ok_or(AgoraError::SkillTagNotFound)?;
+        profile.skill_tags.remove(index);
+        profile.skill_endorsements.remove(index);
+        
+        let profile_key = profile.key();
+        let skill_index = &mut ctx.accounts.skill_index;
+        if let Some(index) = skill_index.profiles.iter().position(|k| *k == profile_key) {
+            skill_index.profiles.swap_remove(index);
+        }
+        
+        msg!("Skill tag removed: {}", tag);
+        Ok(())
+    }
+
+    pub fn migrate_agent_profile(ctx: Context<MigrateAgentProfile>) -> Result<()> {
+        let info = ctx.accounts.profile.to_account_info();
+        let profile = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == *AgentProfile::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            require!(data[8] < AgentProfile::VERSION, AgoraError::ProfileAlreadyMigrated);
+            let old = AgentProfileV1::deserialize(&mut &data[8..])?;
+            AgentProfile {
+                version: AgentProfile::VERSION,
+                owner: old.owner,
+                name: old.name,
+                bio_uri: old.bio_uri,
+                contact_uri: old.contact_uri,
+                skill_endorsements: vec![0; old.skill_tags.len()],
+                skill_tags: old.skill_tags,
+                tasks_posted: old.tasks_posted,
+                tasks_completed: old.tasks_completed,
+                total_earned: old.total_earned,
+                total_spent: old.total_spent,
+                rating_sum: old.rating_sum,
+                rating_count: old.rating_count,
+                weighted_rating_sum: old.weighted_rating_sum,
+                weighted_amount: old.weighted_amount,
+                reputation_updated_at: old.reputation_updated_at,
+                open_tasks: old.open_tasks,
+                active_escrows: old.active_escrows,
+                created_at: old.created_at,
+                endorsement_count: 0,
+                active_contracts: 0,
+                disputes_lost: 0,
+                referrer: None,
+                referral_paid: false,
+            }
+        };
+        
+        let new_len = profile.current_space();
+        let rent = Rent::get()?.minimum_balance(new_len);
+        let shortfall = rent.saturating_sub(info.lamports());
+        if shortfall > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: info.clone(),
+                },
+            );
+            system_program::transfer(cpi_ctx, shortfall)?;
+        }
+        if new_len > info.data_len() {
+            info.resize(new_len)?;
+        }
+        profile.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        
+        msg!("Agent profile migrated to version {}", AgentProfile::VERSION);
+        Ok(())
+    }
+
+    pub fn close_agent_profile(ctx: Context<CloseAgentProfile>) -> Result<()> {
+        msg!("Agent profile closed: {}", ctx.accounts.profile.name);
+        Ok(())
+    }
+
     pub fn submit_review(
         ctx: Context<SubmitReview>,
         rating: u8,
         review_text: String,
     ) -> Result<()> {
-        require!(rating >= 1 && rating <= 5, AgoraError::InvalidRating);
+        require!((1..=5).contains(&rating), AgoraError::InvalidRating);
         require!(review_text.len() <= Review::MAX_REVIEW_LEN, AgoraError::ReviewTooLong);
         
         let clock = Clock::get()?;
+        // Accepting a bid sets the budget to the escrowed amount.
+        let weight = ctx.accounts.task.budget;
         
         let review = &mut ctx.accounts.review;
         review.reviewer = ctx.accounts.reviewer.key();
         review.reviewee = ctx.accounts.reviewee.key();
         review.task = ctx.accounts.task.key();
         review.rating = rating;
+        review.weight = weight;
         review.review_text = review_text;
         review.created_at = clock.unix_timestamp;
+        review.response = String::new();
+        review.responded_at = 0;
+        review.flagged_by = None;
+        review.flagged_at = 0;
         
         let profile = &mut ctx.accounts.reviewee_profile;
-        profile.rating_sum = profile.rating_sum + rating as u32;
-        profile.rating_count = profile.rating_count + 1;
+        profile.rating_sum = profile
+            .rating_sum
+            .checked_add(rating as u32)
+            .ok_or(AgoraError::MathOverflow)?;
+        profile.rating_count = profile
+            .rating_count
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        profile.record_rating(rating, weight, clock.unix_timestamp)?;
         
         msg!("Review submitted: {} stars", rating);
         Ok(())
     }
+
+    pub fn respond_to_review(ctx: Context<RespondToReview>, response: String) -> Result<()> {
+        require!(!response.is_empty(), AgoraError::EmptyResponse);
+        require!(response.len() <= Review::MAX_RESPONSE_LEN, AgoraError::ResponseTooLong);
+        
+        let review = &mut ctx.accounts.review;
+        require!(review.response.is_empty(), AgoraError::AlreadyResponded);
+        
+        review.response = response;
+        review.responded_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Response added to review of {:?}", review.reviewee);
+        Ok(())
+    }
+
+    pub fn flag_review(ctx: Context<FlagReview>) -> Result<()> {
+        let review = &mut ctx.accounts.review;
+        let now = Clock::get()?.unix_timestamp;
+        
+        ctx.accounts.reviewee_profile.remove_rating(
+            review.rating,
+            review.weight,
+            review.created_at,
+            now,
+        )?;
+        
+        review.flagged_by = Some(ctx.accounts.moderator.key());
+        review.flagged_at = now;
+        
+        msg!("Review flagged by {:?}", ctx.accounts.moderator.key());
+        Ok(())
+    }
+
+    pub fn endorse_skill(ctx: Context<EndorseSkill>, skill: String) -> Result<()> {
+        let profile = &mut ctx.accounts.freelancer_profile;
+        let index = profile
+            .skill_tags
+            .iter()
+            .position(|t| *t == skill)
+            .ok_or(AgoraError::SkillTagNotFound)?;
+        
+        profile.skill_endorsements[index] = profile.skill_endorsements[index]
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        profile.endorsement_count = profile
+            .endorsement_count
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        
+        let endorsement = &mut ctx.accounts.endorsement;
+        endorsement.endorser = ctx.accounts.client.key();
+        endorsement.endorsee = ctx.accounts.bid.bidder;
+        endorsement.task = ctx.accounts.task.key();
+        endorsement.skill = skill;
+        endorsement.created_at = Clock::get()?.unix_timestamp;
+        endorsement.bump = ctx.bumps.endorsement;
+        
+        msg!("Skill endorsed: {}", endorsement.skill);
+        Ok(())
+    }
+
+    pub fn get_reputation(ctx: Context<GetReputation>) -> Result<ReputationView> {
+        let profile = &ctx.accounts.profile;
+        let now = Clock::get()?.unix_timestamp;
+        let (_, weighted_amount) = profile.decayed_reputation(now);
+        
+        Ok(ReputationView {
+            profile: profile.key(),
+            score: profile.reputation_score(now),
+            weighted_amount,
+            rating_count: profile.rating_count,
+            as_of: now,
+        })
+    }
+
+    pub fn block_bidder(ctx: Context<BlockBidder>, bidder: Pubkey) -> Result<()> {
+        let blocklist = &mut ctx.accounts.blocklist;
+        if blocklist.owner == Pubkey::default() {
+            blocklist.owner = ctx.accounts.owner.key();
+            blocklist.bump = ctx.bumps.blocklist;
+        }
+        
+        require!(!blocklist.blocked.contains(&bidder), AgoraError::AlreadyBlocked);
+        require!(blocklist.blocked.len() < Blocklist::MAX_BLOCKED, AgoraError::BlocklistFull);
+        
+        blocklist.blocked.push(bidder);
+        
+        msg!("Bidder blocked: {:?}", bidder);
+        Ok(())
+    }
+
+    pub fn unblock_bidder(ctx: Context<UnblockBidder>, bidder: Pubkey) -> Result<()> {
+        let blocklist = &mut ctx.accounts.blocklist;
+        
+        let index = blocklist
+            .blocked
+            .iter()
+            .position(|k| *k == bidder)
+            .ok_or(AgoraError::NotBlocked)?;
+        blocklist.blocked.remove(index);
+        
+        msg!("Bidder unblocked: {:?}", bidder);
+        Ok(())
+    }
+
+    pub fn deposit_stake(ctx: Context<DepositStake>, amount: u64) -> Result<()> {
+        require!(amount > 0, AgoraError::InvalidAmount);
+        
+        let stake_vault = &mut ctx.accounts.stake_vault;
+        if stake_vault.owner == Pubkey::default() {
+            stake_vault.profile = ctx.accounts.profile.key();
+            stake_vault.owner = ctx.accounts.owner.key();
+            stake_vault.bump = ctx.bumps.stake_vault;
+        }
+        
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.owner.to_account_info(),
+                to: stake_vault.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_ctx, amount)?;
+        
+        stake_vault.amount = stake_vault
+            .amount
+            .checked_add(amount)
+            .ok_or(AgoraError::MathOverflow)?;
+        
+        msg!("Stake deposited: {}", amount);
+        Ok(())
+    }
+
+    pub fn request_unstake(ctx: Context<ManageStake>, amount: u64) -> Result<()> {
+        let stake_vault = &mut ctx.accounts.stake_vault;
+        
+        require!(stake_vault.locked_contracts == 0, AgoraError::StakeLocked);
+        require!(
+            amount > 0 && amount <= stake_vault.amount,
+            AgoraError::InvalidAmount
+        );
+        
+        // A new request replaces any pending one and restarts the cooldown.
+        stake_vault.pending_unstake = amount;
+        stake_vault.unstake_requested_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Unstake requested: {}", amount);
+        Ok(())
+    }
+
+    pub fn withdraw_stake(ctx: Context<ManageStake>) -> Result<()> {
+        let stake_vault = &mut ctx.accounts.stake_vault;
+        let clock = Clock::get()?;
+        
+        require!(stake_vault.pending_unstake > 0, AgoraError::NoPendingUnstake);
+        require!(stake_vault.locked_contracts == 0, AgoraError::StakeLocked);
+        require!(
+            clock.unix_timestamp >= stake_vault.unstake_requested_at + StakeVault::UNSTAKE_COOLDOWN,
+            AgoraError::UnstakeCooldown
+        );
+        
+        // Slashing may have shrunk the stake since the request.
+        let amount = stake_vault.pending_unstake.min(stake_vault.amount);
+        stake_vault.amount -= amount;
+        stake_vault.pending_unstake = 0;
+        
+        stake_vault.sub_lamports(amount)?;
+        ctx.accounts.owner.add_lamports(amount)?;
+        
+        msg!("Stake withdrawn: {}", amount);
+        Ok(())
+    }
+
+    pub fn release_stake_lock(ctx: Context<ReleaseStakeLock>) -> Result<()> {
+        ctx.accounts.stake_vault.unlock();
+        ctx.accounts.bid.stake_locked = false;
+        
+        msg!("Stake lock released for bid {:?}", ctx.accounts.bid.key());
+        Ok(())
+    }
+
+    pub fn raise_dispute(ctx: Context<RaiseDispute>, reason: String) -> Result<()> {
+        require!(reason.len() <= Dispute::MAX_REASON_LEN, AgoraError::DisputeReasonTooLong);
+        
+        let clock = Clock::get()?;
+        let raised_by = match &ctx.accounts.delegation {
+            Some(delegation) => {
+                delegation.authorize(Delegation::SUBMIT_EVIDENCE, clock.unix_timestamp)?;
+                delegation.owner
+            }
+            None => ctx.accounts.party.key(),
+        };
+        
+        let task = &mut ctx.accounts.task;
+        task.status = TaskStatus::Disputed;
+        task.updated_at = clock.unix_timestamp;
+        
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.task = task.key();
+        dispute.raised_by = raised_by;
+        dispute.reason = reason;
+        dispute.raised_at = clock.unix_timestamp;
+        dispute.ruling = None;
+        dispute.slashed = 0;
+        dispute.resolved_by = None;
+        dispute.resolved_at = 0;
+        dispute.bump = ctx.bumps.dispute;
+        
+        msg!("Dispute raised on task: {}", task.title);
+        Ok(())
+    }
+
+    pub fn resolve_dispute(
+        ctx: Context<ResolveDispute>,
+        ruling: DisputeRuling,
+        slash_bps: u16,
+    ) -> Result<()> {
+        require!(slash_bps <= 10_000, AgoraError::InvalidSlash);
+        require!(
+            ctx.accounts.freelancer_stake.is_some() == ctx.accounts.bid.stake_locked,
+            AgoraError::StakeMismatch
+        );
+        
+        let clock = Clock::get()?;
+        let task = &mut ctx.accounts.task;
+        let escrow = &mut ctx.accounts.escrow;
+        let remaining = escrow
+            .total_amount
+            .checked_sub(escrow.released_amount)
+            .ok_or(AgoraError::MathOverflow)?;
+        
+        let recipient = match ruling {
+            DisputeRuling::Client => ctx.accounts.client_token_account.to_account_info(),
+            DisputeRuling::Freelancer => ctx.accounts.freelancer_token_account.to_account_info(),
+        };
+        
+        if remaining > 0 {
+            let task_key = task.key();
+            let seeds = &[b"escrow", task_key.as_ref(), &[escrow.bump]];
+            let signer = &[&seeds[..]];
+            
+            let transfer_instruction = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: recipient,
+                authority: escrow.to_account_info(),
+            };
+            
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+                signer,
+            );
+            
+            token::transfer(cpi_ctx, remaining)?;
+        }
+        
+        let client_profile = &mut ctx.accounts.client_profile;
+        let freelancer_profile = &mut ctx.accounts.freelancer_profile;
+        let mut slashed = 0;
+        
+        match ruling {
+            DisputeRuling::Client => {
+                // The escrow now only accounts for what was actually paid out.
+                escrow.total_amount = escrow.released_amount;
+                task.status = TaskStatus::Cancelled;
+                
+                freelancer_profile.disputes_lost = freelancer_profile
+                    .disputes_lost
+                    .checked_add(1)
+                    .ok_or(AgoraError::MathOverflow)?;
+                
+                if let Some(stake) = ctx.accounts.freelancer_stake.as_mut() {
+                    slashed = (stake.amount as u128 * slash_bps as u128 / 10_000) as u64;
+                    stake.amount -= slashed;
+                    stake.pending_unstake = stake.pending_unstake.min(stake.amount);
+                    stake.sub_lamports(slashed)?;
+                    ctx.accounts.client.add_lamports(slashed)?;
+                }
+            }
+            DisputeRuling::Freelancer => {
+                escrow.released_amount = escrow.total_amount;
+                task.status = TaskStatus::Completed;
+                for milestone in task.milestones.iter_mut() {
+                    milestone.paid = true;
+                }
+                
+                client_profile.total_spent = client_profile
+                    .total_spent
+                    .checked_add(remaining)
+                    .ok_or(AgoraError::MathOverflow)?;
+                freelancer_profile.total_earned = freelancer_profile
+                    .total_earned
+                    .checked_add(remaining)
+                    .ok_or(AgoraError::MathOverflow)?;
+                freelancer_profile.tasks_completed = freelancer_profile
+                    .tasks_completed
+                    .checked_add(1)
+                    .ok_or(AgoraError::MathOverflow)?;
+            }
+        }
+        task.updated_at = clock.unix_timestamp;
+        
+        client_profile.open_tasks = client_profile.open_tasks.saturating_sub(1);
+        client_profile.active_escrows = client_profile.active_escrows.saturating_sub(1);
+        freelancer_profile.active_escrows = freelancer_profile.active_escrows.saturating_sub(1);
+        freelancer_profile.active_contracts = freelancer_profile.active_contracts.saturating_sub(1);
+        
+        if let Some(stake) = ctx.accounts.freelancer_stake.as_mut() {
+            stake.unlock();
+            ctx.accounts.bid.stake_locked = false;
+        }
+        
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.ruling = Some(ruling);
+        dispute.slashed = slashed;
+        dispute.resolved_by = Some(ctx.accounts.arbiter.key());
+        dispute.resolved_at = clock.unix_timestamp;
+        
+        msg!("Dispute resolved for {:?}, slashed {}", ruling, slashed);
+        Ok(())
+    }
+
+    pub fn initialize_agent_manifest(
+        ctx: Context<InitializeAgentManifest>,
+        params: ManifestParams,
+    ) -> Result<()> {
+        let manifest = &mut ctx.accounts.manifest;
+        manifest.profile = ctx.accounts.profile.key();
+        manifest.owner = ctx.accounts.owner.key();
+        manifest.revision = 0;
+        manifest.bump = ctx.bumps.manifest;
+        manifest.apply(params, Clock::get()?.unix_timestamp)?;
+        
+        msg!("Agent manifest created for {:?}", manifest.owner);
+        Ok(())
+    }
+
+    pub fn update_agent_manifest(
+        ctx: Context<UpdateAgentManifest>,
+        params: ManifestParams,
+    ) -> Result<()> {
+        let manifest = &mut ctx.accounts.manifest;
+        manifest.revision = manifest
+            .revision
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        manifest.apply(params, Clock::get()?.unix_timestamp)?;
+        
+        msg!("Agent manifest updated to revision {}", manifest.revision);
+        Ok(())
+    }
+
+    pub fn create_delegation(
+        ctx: Context<CreateDelegation>,
+        delegate: Pubkey,
+        permissions: u8,
+        max_bid_amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            permissions != 0 && permissions & !Delegation::ALL_PERMISSIONS == 0,
+            AgoraError::InvalidPermissions
+        );
+        require!(delegate != ctx.accounts.owner.key(), AgoraError::Unauthorized);
+        
+        let clock = Clock::get()?;
+        require!(expires_at > clock.unix_timestamp, AgoraError::InvalidDeadline);
+        
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.profile = ctx.accounts.profile.key();
+        delegation.owner = ctx.accounts.owner.key();
+        delegation.delegate = delegate;
+        delegation.permissions = permissions;
+        delegation.max_bid_amount = max_bid_amount;
+        delegation.expires_at = expires_at;
+        delegation.created_at = clock.unix_timestamp;
+        delegation.bump = ctx.bumps.delegation;
+        
+        msg!("Delegation created for {:?}", delegate);
+        Ok(())
+    }
+
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        msg!("Delegation revoked for {:?}", ctx.accounts.delegation.delegate);
+        Ok(())
+    }
+
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = admin;
+        config.arbiter = admin;
+        config.treasury = admin;
+        config.protocol_fee_bps = 0;
+        config.referral_share_bps = 0;
+        config.bump = ctx.bumps.config;
+        
+        msg!("Marketplace config initialized, admin: {:?}", admin);
+        Ok(())
+    }
+
+    pub fn set_arbiter(ctx: Context<SetArbiter>, arbiter: Pubkey) -> Result<()> {
+        ctx.accounts.config.arbiter = arbiter;
+        
+        msg!("Arbiter set: {:?}", arbiter);
+        Ok(())
+    }
+
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        treasury: Pubkey,
+        protocol_fee_bps: u16,
+        referral_share_bps: u16,
+    ) -> Result<()> {
+        require!(protocol_fee_bps <= 10_000, AgoraError::InvalidFee);
+        require!(referral_share_bps <= 10_000, AgoraError::InvalidFee);
+        
+        let config = &mut ctx.accounts.config;
+        config.treasury = treasury;
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.referral_share_bps = referral_share_bps;
+        
+        msg!("Fees set: {} bps, referral share {} bps", protocol_fee_bps, referral_share_bps);
+        Ok(())
+    }
+
+    pub fn create_badge_definition(
+        ctx: Context<CreateBadgeDefinition>,
+        id: u32,
+        name: String,
+        uri: String,
+        criteria: BadgeCriteria,
+    ) -> Result<()> {
+        require!(name.len() <= BadgeDefinition::MAX_NAME_LEN, AgoraError::NameTooLong);
+        require!(uri.len() <= AgentProfile::MAX_URI_LEN, AgoraError::UriTooLong);
+        
+        let definition = &mut ctx.accounts.definition;
+        definition.id = id;
+        definition.name = name;
+        definition.uri = uri;
+        definition.criteria = criteria;
+        definition.issued = 0;
+        definition.bump = ctx.bumps.definition;
+        
+        msg!("Badge defined: {}", definition.name);
+        Ok(())
+    }
+
+    pub fn claim_badge(ctx: Context<ClaimBadge>) -> Result<()> {
+        let definition = &mut ctx.accounts.definition;
+        let profile = &ctx.accounts.profile;
+        
+        require!(
+            definition.criteria.is_met_by(profile),
+            AgoraError::BadgeCriteriaNotMet
+        );
+        
+        definition.issued = definition
+            .issued
+            .checked_add(1)
+            .ok_or(AgoraError::MathOverflow)?;
+        
+        let badge = &mut ctx.accounts.badge;
+        badge.definition = definition.key();
+        badge.profile = profile.key();
+        badge.owner = profile.owner;
+        badge.awarded_at = Clock::get()?.unix_timestamp;
+        badge.bump = ctx.bumps.badge;
+        
+        msg!("Badge {} awarded to {:?}", definition.name, profile.owner);
+        Ok(())
+    }
 }
@@ -10,6 +10,7 @@ import {
   getAccount,
 } from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 // Helper function to add delay between requests (avoid rate limiting)
 const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
@@ -19,32 +20,87 @@ const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
 interface AgoraProgram extends Program {
   methods: {
     initializeAgentProfile: (name: string) => any;
+    updateAgentProfile: (name: string, bioUri: string, contactUri: string) => any;
+    migrateAgentProfile: () => any;
+    closeAgentProfile: () => any;
+    addSkillTag: (tag: string) => any;
+    removeSkillTag: (tag: string) => any;
+    endorseSkill: (skill: string) => any;
     postTask: (
       title: string,
       description: string,
       budget: anchor.BN,
       milestones: Milestone[],
-      deadline: anchor.BN
+      deadline: anchor.BN,
+      requirements: BidRequirements | null,
+      category: TaskCategory,
+      categoryPage: number,
+      metadata: TaskMetadata | null
     ) => any;
     updateTask: (
       description: string | null,
       budget: anchor.BN | null,
-      deadline: anchor.BN | null
+      deadline: anchor.BN | null,
+      metadata: TaskMetadata | null,
+      milestoneOps: MilestoneOp[]
     ) => any;
     cancelTask: () => any;
+    transferTaskOwnership: (newOwner: PublicKey) => any;
+    acceptTaskOwnership: () => any;
+    addInvitee: (invitee: PublicKey) => any;
+    removeInvitee: (invitee: PublicKey) => any;
+    expireTask: () => any;
     submitBid: (
       amount: anchor.BN,
       timeline: anchor.BN,
-      proposal: string
+      proposal: string,
+      milestoneAmounts: anchor.BN[]
     ) => any;
     acceptBid: () => any;
+    counterOffer: (amount: anchor.BN, timeline: anchor.BN) => any;
+    acceptCounter: () => any;
     rejectBid: () => any;
     withdrawBid: () => any;
+    closeExpiredBid: () => any;
+    reclaimBidBond: () => any;
+    forfeitBidBond: () => any;
     fundEscrow: () => any;
     completeMilestone: (milestoneIndex: number) => any;
-    releasePayment: (milestoneIndex: number) => any;
+    setReleaseApprovers: (approvers: PublicKey[], threshold: number) => any;
+    approveRelease: (milestoneIndex: number) => any;
+    revokeApproval: (milestoneIndex: number) => any;
     requestRefund: () => any;
     submitReview: (rating: number, reviewText: string) => any;
+    respondToReview: (response: string) => any;
+    flagReview: () => any;
+    getReputation: () => any;
+    createDelegation: (
+      delegate: PublicKey,
+      permissions: number,
+      maxBidAmount: anchor.BN,
+      expiresAt: anchor.BN
+    ) => any;
+    revokeDelegation: () => any;
+    initializeAgentManifest: (params: ManifestParams) => any;
+    updateAgentManifest: (params: ManifestParams) => any;
+    initializeConfig: (admin: PublicKey) => any;
+    setArbiter: (arbiter: PublicKey) => any;
+    setFees: (treasury: PublicKey, protocolFeeBps: number, referralShareBps: number) => any;
+    createBadgeDefinition: (
+      id: number,
+      name: string,
+      uri: string,
+      criteria: BadgeCriteria
+    ) => any;
+    claimBadge: () => any;
+    blockBidder: (bidder: PublicKey) => any;
+    unblockBidder: (bidder: PublicKey) => any;
+    depositStake: (amount: anchor.BN) => any;
+    requestUnstake: (amount: anchor.BN) => any;
+    withdrawStake: () => any;
+    releaseStakeLock: () => any;
+    raiseDispute: (reason: string) => any;
+    resolveDispute: (ruling: DisputeRuling, slashBps: number) => any;
   };
   account: {
     agentProfile: {
@@ -56,26 +112,198 @@ interface AgoraProgram extends Program {
     bid: {
       fetch: (address: PublicKey) => Promise<Bid>;
     };
+    bidBond: {
+      fetch: (address: PublicKey) => Promise<BidBond>;
+    };
     escrow: {
       fetch: (address: PublicKey) => Promise<Escrow>;
     };
     review: {
       fetch: (address: PublicKey) => Promise<Review>;
     };
+    skillIndex: {
+      fetch: (address: PublicKey) => Promise<SkillIndex>;
+    };
+    endorsement: {
+      fetch: (address: PublicKey) => Promise<Endorsement>;
+    };
+    delegation: {
+      fetch: (address: PublicKey) => Promise<Delegation>;
+    };
+    agentManifest: {
+      fetch: (address: PublicKey) => Promise<AgentManifest>;
+    };
+    marketplaceConfig: {
+      fetch: (address: PublicKey) => Promise<MarketplaceConfig>;
+      fetchNullable: (address: PublicKey) => Promise<MarketplaceConfig | null>;
+    };
+    badgeDefinition: {
+      fetch: (address: PublicKey) => Promise<BadgeDefinition>;
+    };
+    badge: {
+      fetch: (address: PublicKey) => Promise<Badge>;
+    };
+    blocklist: {
+      fetch: (address: PublicKey) => Promise<Blocklist>;
+    };
+    stakeVault: {
+      fetch: (address: PublicKey) => Promise<StakeVault>;
+    };
+    categoryHead: {
+      fetchNullable: (address: PublicKey) => Promise<CategoryHead | null>;
+    };
+    categoryIndex: {
+      fetch: (address: PublicKey) => Promise<CategoryIndex>;
+    };
+    dispute: {
+      fetch: (address: PublicKey) => Promise<Dispute>;
+    };
   };
 }
 
 // Account state interfaces
 interface AgentProfile {
+  version: number;
   owner: PublicKey;
   name: string;
+  bioUri: string;
+  contactUri: string;
+  skillTags: string[];
+  skillEndorsements: number[];
+  endorsementCount: number;
   tasksPosted: number;
   tasksCompleted: number;
   totalEarned: anchor.BN;
   totalSpent: anchor.BN;
   ratingSum: number;
   ratingCount: number;
+  weightedRatingSum: anchor.BN;
+  weightedAmount: anchor.BN;
+  reputationUpdatedAt: anchor.BN;
+  openTasks: number;
+  activeEscrows: number;
+  activeContracts: number;
+  disputesLost: number;
+  referrer: PublicKey | null;
+  referralPaid: boolean;
+  createdAt: anchor.BN;
+}
+
+type TaskCategory =
+  | { development: {} }
+  | { design: {} }
+  | { writing: {} }
+  | { research: {} }
+  | { dataLabeling: {} }
+  | { audit: {} }
+  | { marketing: {} }
+  | { other: {} };
+
+const TASK_CATEGORIES = [
+  "development",
+  "design",
+  "writing",
+  "research",
+  "dataLabeling",
+  "audit",
+  "marketing",
+  "other",
+];
+
+interface CategoryHead {
+  category: TaskCategory;
+  currentPage: number;
+  nextPage: number;
+  freePages: number[];
+  bump: number;
+}
+
+interface CategoryIndex {
+  category: TaskCategory;
+  page: number;
+  tasks: PublicKey[];
+  bump: number;
+}
+
+interface MintPrice {
+  mint: PublicKey;
+  minAmount: anchor.BN;
+}
+
+interface ManifestParams {
+  categories: TaskCategory[];
+  maxConcurrentTasks: number;
+  minMilestoneAmount: anchor.BN;
+  mintPricing: MintPrice[];
+  available: boolean;
+  extendedManifestHash: number[];
+}
+
+interface AgentManifest {
+  profile: PublicKey;
+  owner: PublicKey;
+  schemaVersion: number;
+  revision: number;
+  categories: TaskCategory[];
+  maxConcurrentTasks: number;
+  minMilestoneAmount: anchor.BN;
+  mintPricing: MintPrice[];
+  available: boolean;
+  extendedManifestHash: number[];
+  updatedAt: anchor.BN;
+  bump: number;
+}
+
+interface Delegation {
+  profile: PublicKey;
+  owner: PublicKey;
+  delegate: PublicKey;
+  permissions: number;
+  maxBidAmount: anchor.BN;
+  expiresAt: anchor.BN;
   createdAt: anchor.BN;
+  bump: number;
+}
+
+interface MarketplaceConfig {
+  admin: PublicKey;
+  arbiter: PublicKey;
+  treasury: PublicKey;
+  protocolFeeBps: number;
+  referralShareBps: number;
+  bump: number;
+}
+
+interface BadgeCriteria {
+  minTasksCompleted: number;
+  minTotalEarned: anchor.BN;
+  minAverageRating: number;
+  maxDisputesLost: number | null;
+}
+
+interface BadgeDefinition {
+  id: number;
+  name: string;
+  uri: string;
+  criteria: BadgeCriteria;
+  issued: number;
+  bump: number;
+}
+
+interface Badge {
+  definition: PublicKey;
+  profile: PublicKey;
+  owner: PublicKey;
+  awardedAt: anchor.BN;
+  bump: number;
+}
+
+interface ReputationView {
+  profile: PublicKey;
+  score: anchor.BN;
+  weightedAmount: anchor.BN;
+  ratingCount: number;
+  asOf: anchor.BN;
 }
 
 interface Milestone {
@@ -83,9 +311,73 @@ interface Milestone {
   amount: anchor.BN;
   completed: boolean;
   paid: boolean;
+  approvals: number;
+}
+
+type TaskVisibility = { public: {} } | { inviteOnly: {} };
+
+interface BidRequirements {
+  bidBond: anchor.BN;
+  visibility: TaskVisibility;
+  minTasksCompleted: number;
+  minAverageRating: number;
+  requiredSkills: string[];
+  minSkillEndorsements: number;
+  minStake: anchor.BN;
+}
+
+interface Blocklist {
+  owner: PublicKey;
+  blocked: PublicKey[];
+  bump: number;
+}
+
+interface StakeVault {
+  profile: PublicKey;
+  owner: PublicKey;
+  amount: anchor.BN;
+  pendingUnstake: anchor.BN;
+  unstakeRequestedAt: anchor.BN;
+  lockedContracts: number;
+  bump: number;
+}
+
+type DisputeRuling = { client: {} } | { freelancer: {} };
+
+interface Dispute {
+  task: PublicKey;
+  raisedBy: PublicKey;
+  reason: string;
+  raisedAt: anchor.BN;
+  ruling: DisputeRuling | null;
+  slashed: anchor.BN;
+  resolvedBy: PublicKey | null;
+  resolvedAt: anchor.BN;
+  bump: number;
+}
+
+interface Endorsement {
+  endorser: PublicKey;
+  endorsee: PublicKey;
+  task: PublicKey;
+  skill: string;
+  createdAt: anchor.BN;
+  bump: number;
+}
+
+interface SkillIndex {
+  tag: string;
+  profiles: PublicKey[];
+  bump: number;
 }
 
-type TaskStatus = { open: {} } | { inProgress: {} } | { completed: {} } | { cancelled: {} } | { disputed: {} };
+type TaskStatus =
+  | { open: {} }
+  | { inProgress: {} }
+  | { completed: {} }
+  | { cancelled: {} }
+  | { disputed: {} }
+  | { expired: {} };
 
 interface Task {
   owner: PublicKey;
@@ -97,8 +389,27 @@ interface Task {
   status: TaskStatus;
   acceptedBid: PublicKey | null;
   escrowAccount: PublicKey | null;
+  requirements: BidRequirements;
+  invitees: PublicKey[];
+  category: TaskCategory;
+  categoryPage: number;
+  scopeVersion: number;
+  pendingOwner: PublicKey | null;
+  approvers: PublicKey[];
+  approvalThreshold: number;
   createdAt: anchor.BN;
   updatedAt: anchor.BN;
+  metadata: TaskMetadata | null;
+}
+
+type MilestoneOp =
+  | { add: { description: string; amount: anchor.BN } }
+  | { edit: { index: number; description: string | null; amount: anchor.BN | null } }
+  | { remove: { index: number } };
+
+interface TaskMetadata {
+  uri: string;
+  contentHash: number[];
 }
 
 type BidStatus = { pending: {} } | { accepted: {} } | { rejected: {} } | { withdrawn: {} };
@@ -109,8 +420,30 @@ interface Bid {
   amount: anchor.BN;
   timeline: anchor.BN;
   proposal: string;
+  milestoneAmounts: anchor.BN[];
+  offers: Offer[];
+  bond: anchor.BN;
   status: BidStatus;
   createdAt: anchor.BN;
+  acceptedAt: anchor.BN;
+  expiresAt: anchor.BN;
+  stakeLocked: boolean;
+  scopeVersion: number;
+  deliveredAt: anchor.BN;
+}
+
+interface Offer {
+  amount: anchor.BN;
+  timeline: anchor.BN;
+  proposedBy: PublicKey;
+  createdAt: anchor.BN;
+}
+
+interface BidBond {
+  bid: PublicKey;
+  bidder: PublicKey;
+  amount: anchor.BN;
+  bump: number;
 }
 
 interface Escrow {
@@ -128,8 +461,13 @@ interface Review {
   reviewee: PublicKey;
   task: PublicKey;
   rating: number;
+  weight: anchor.BN;
   reviewText: string;
   createdAt: anchor.BN;
+  response: string;
+  respondedAt: anchor.BN;
+  flaggedBy: PublicKey | null;
+  flaggedAt: anchor.BN;
 }
 
 // Helper function to get discriminant from status object
@@ -139,6 +477,7 @@ function getTaskStatus(status: TaskStatus): string {
   if ('completed' in status) return 'Completed';
   if ('cancelled' in status) return 'Cancelled';
   if ('disputed' in status) return 'Disputed';
+  if ('expired' in status) return 'Expired';
   return 'Unknown';
 }
 
@@ -164,7 +503,182 @@ describe("Agora - Agent Task Marketplace", () => {
   const MAX_MILESTONES = 10;
   const MAX_PROPOSAL_LEN = 2000;
   const MAX_NAME_LEN = 50;
+  const MAX_URI_LEN = 200;
   const MAX_REVIEW_LEN = 1000;
+  const REPUTATION_SCALE = 1_000_000;
+  const PERMISSION_SUBMIT_BID = 1;
+  const PERMISSION_COMPLETE_MILESTONE = 2;
+  const PERMISSION_SUBMIT_EVIDENCE = 4;
+
+  const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+
+  // The deploying wallet is the upgrade authority, so it sets up the config
+  // once and acts as its admin for every test.
+  before(async () => {
+    const existing = await program.account.marketplaceConfig.fetchNullable(configPda);
+    if (existing) return;
+    
+    const [programDataPda] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_ID
+    );
+    await program.methods
+      .initializeConfig(provider.wallet.publicKey)
+      .accounts({
+        authority: provider.wallet.publicKey,
+        config: configPda,
+        program: program.programId,
+        programData: programDataPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  // Derive the badge definition PDA from its numeric id
+  const getBadgeDefinitionPda = (id: number): PublicKey => {
+    const idBytes = Buffer.alloc(4);
+    idBytes.writeUInt32LE(id);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("badge_definition"), idBytes],
+      program.programId
+    )[0];
+  };
+
+  const getBadgePda = (definition: PublicKey, profile: PublicKey): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("badge"), definition.toBuffer(), profile.toBuffer()],
+      program.programId
+    )[0];
+
+  // Ids must be unique per validator run, since definitions are never closed
+  const createBadgeDefinition = async (name: string, criteria: BadgeCriteria): Promise<PublicKey> => {
+    const id = Math.floor(Math.random() * 0xffffffff);
+    const definition = getBadgeDefinitionPda(id);
+    await program.methods
+      .createBadgeDefinition(id, name, "https://example.com/badges/" + id, criteria)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        config: configPda,
+        definition,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    return definition;
+  };
+
+  // Derive the profile PDA for an agent
+  const getProfilePda = (owner: PublicKey): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), owner.toBuffer()],
+      program.programId
+    )[0];
+
+  // Derive the skill index PDA, keyed by the hash of the tag
+  const getSkillIndexPda = (tag: string): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("skill"), createHash("sha256").update(tag).digest()],
+      program.programId
+    )[0];
+
+  // Derive the endorsement PDA for a (task, skill) pair
+  const getEndorsementPda = (task: PublicKey, skill: string): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("endorsement"),
+        task.toBuffer(),
+        createHash("sha256").update(skill).digest(),
+      ],
+      program.programId
+    )[0];
+
+  // Derive a task owner's blocklist PDA
+  const getBlocklistPda = (owner: PublicKey): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("blocklist"), owner.toBuffer()],
+      program.programId
+    )[0];
+
+  // Posting tasks and receiving payments update profile stats, so both parties need one
+  const createProfile = async (
+    owner: Keypair,
+    name: string,
+    referrerProfile: PublicKey | null = null
+  ): Promise<PublicKey> => {
+    const profilePda = getProfilePda(owner.publicKey);
+    await program.methods
+      .initializeAgentProfile(name)
+      .accounts({
+        owner: owner.publicKey,
+        profile: profilePda,
+        referrerProfile,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+    return profilePda;
+  };
+
+  const getStakeVaultPda = (profile: PublicKey): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("stake"), profile.toBuffer()],
+      program.programId
+    )[0];
+
+  const getCategoryIndexPda = (category: TaskCategory, page: number): PublicKey => {
+    const pageBytes = Buffer.alloc(4);
+    pageBytes.writeUInt32LE(page);
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("category"),
+        Buffer.from([TASK_CATEGORIES.indexOf(Object.keys(category)[0])]),
+        pageBytes,
+      ],
+      program.programId
+    )[0];
+  };
+
+  const getCategoryHeadPda = (category: TaskCategory): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("category_head"), Buffer.from([TASK_CATEGORIES.indexOf(Object.keys(category)[0])])],
+      program.programId
+    )[0];
+
+  // New tasks go on the category's current page
+  const currentCategoryPage = async (
+    category: TaskCategory = { development: {} }
+  ): Promise<number> => {
+    const head = await program.account.categoryHead.fetchNullable(getCategoryHeadPda(category));
+    return head ? head.currentPage : 0;
+  };
+
+  const categoryPostAccounts = async (category: TaskCategory = { development: {} }) => ({
+    categoryHead: getCategoryHeadPda(category),
+    categoryIndex: getCategoryIndexPda(category, await currentCategoryPage(category)),
+  });
+
+  // The index page listing an open task
+  const categoryIndexFor = async (task: PublicKey): Promise<PublicKey> => {
+    const { category, categoryPage } = await program.account.task.fetch(task);
+    return getCategoryIndexPda(category, categoryPage);
+  };
+
+  // Accounts for taking an open task off its category index
+  const categoryAccountsFor = async (task: PublicKey) => ({
+    categoryHead: getCategoryHeadPda((await program.account.task.fetch(task)).category),
+    categoryIndex: await categoryIndexFor(task),
+  });
+
+  // Derive the review PDA; each party can review a task once
+  const getReviewPda = (task: PublicKey, reviewer: PublicKey): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("review"), task.toBuffer(), reviewer.toBuffer()],
+      program.programId
+    )[0];
 
   // ============================================================================
   // AGENT PROFILE TESTS
@@ -196,6 +710,7 @@ describe("Agora - Agent Task Marketplace", () => {
         .accounts({
           owner: agent.publicKey,
           profile: profilePda,
+          referrerProfile: null,
           systemProgram: SystemProgram.programId,
         })
         .signers([agent])
@@ -234,6 +749,7 @@ describe("Agora - Agent Task Marketplace", () => {
           .accounts({
             owner: agent.publicKey,
             profile: profilePda,
+            referrerProfile: null,
             systemProgram: SystemProgram.programId,
           })
           .signers([agent])
@@ -266,6 +782,7 @@ describe("Agora - Agent Task Marketplace", () => {
           .accounts({
             owner: agent.publicKey,
             profile: profilePda,
+            referrerProfile: null,
             systemProgram: SystemProgram.programId,
           })
           .signers([agent])
@@ -296,6 +813,7 @@ describe("Agora - Agent Task Marketplace", () => {
         .accounts({
           owner: agent.publicKey,
           profile: profilePda,
+          referrerProfile: null,
           systemProgram: SystemProgram.programId,
         })
         .signers([agent])
@@ -308,6 +826,7 @@ describe("Agora - Agent Task Marketplace", () => {
           .accounts({
             owner: agent.publicKey,
             profile: profilePda,
+            referrerProfile: null,
             systemProgram: SystemProgram.programId,
           })
           .signers([agent])
@@ -317,500 +836,764 @@ describe("Agora - Agent Task Marketplace", () => {
         expect(error.toString()).to.include("custom program error");
       }
     });
-  });
 
-  // ============================================================================
-  // TASK POSTING TESTS
-  // ============================================================================
-  
-  describe("Task Posting", () => {
-    let client: Keypair;
-    let milestones: Milestone[];
-    
-    beforeEach(async () => {
-      client = Keypair.generate();
+    it("should update profile metadata and grow the account", async () => {
+      const agent = Keypair.generate();
       const sig = await provider.connection.requestAirdrop(
-        client.publicKey,
-        5 * anchor.web3.LAMPORTS_PER_SOL
+        agent.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
       );
       await provider.connection.confirmTransaction(sig);
-      await sleep(500); // Delay to avoid rate limiting
+      await sleep(500);
       
-      milestones = [
-        {
-          description: "Initial setup and planning",
-          amount: new anchor.BN(1000000),
-          completed: false,
-          paid: false,
-        },
-        {
-          description: "Development phase",
-          amount: new anchor.BN(2000000),
-          completed: false,
-          paid: false,
-        },
-        {
-          description: "Testing and delivery",
-          amount: new anchor.BN(1000000),
-          completed: false,
-          paid: false,
-        },
-      ];
-    });
-
-    it("should post a task with valid parameters", async () => {
-      const task = Keypair.generate();
-      const title = "Build a Solana dApp";
-      const description = "Create a decentralized application on Solana blockchain with specific features";
-      const budget = new anchor.BN(4000000);
-      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7); // 7 days from now
+      await createProfile(agent, "Before");
+      const profilePda = getProfilePda(agent.publicKey);
+      const sizeBefore = (await provider.connection.getAccountInfo(profilePda)).data.length;
       
       await program.methods
-        .postTask(title, description, budget, milestones, deadline)
+        .updateAgentProfile(
+          "After",
+          "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+          "https://example.com/contact"
+        )
         .accounts({
-          owner: client.publicKey,
-          task: task.publicKey,
+          owner: agent.publicKey,
+          profile: profilePda,
           systemProgram: SystemProgram.programId,
         })
-        .signers([client, task])
+        .signers([agent])
         .rpc();
       
-      // Verify task was created
-      const taskAccount = await program.account.task.fetch(task.publicKey);
-      expect(taskAccount.owner.toBase58()).to.equal(client.publicKey.toBase58());
-      expect(taskAccount.title).to.equal(title);
-      expect(taskAccount.description).to.equal(description);
-      expect(taskAccount.budget.toNumber()).to.equal(budget.toNumber());
-      expect(taskAccount.milestones.length).to.equal(3);
-      expect(taskAccount.deadline.toNumber()).to.equal(deadline.toNumber());
-      expect(getTaskStatus(taskAccount.status)).to.equal("Open");
-      expect(taskAccount.acceptedBid).to.be.null;
-      expect(taskAccount.escrowAccount).to.be.null;
+      const profile = await program.account.agentProfile.fetch(profilePda);
+      expect(profile.version).to.equal(2);
+      expect(profile.name).to.equal("After");
+      expect(profile.bioUri).to.include("ipfs://");
+      expect(profile.contactUri).to.equal("https://example.com/contact");
+      
+      const sizeAfter = (await provider.connection.getAccountInfo(profilePda)).data.length;
+      expect(sizeAfter).to.be.greaterThan(sizeBefore);
     });
 
-    it("should fail to post task with empty title", async () => {
-      const task = Keypair.generate();
-      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+    it("should fail to update profile with URI exceeding max length", async () => {
+      const agent = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        agent.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(agent, "Agent");
       
       try {
         await program.methods
-          .postTask("", "Description", new anchor.BN(1000000), milestones, deadline)
+          .updateAgentProfile("Agent", "a".repeat(MAX_URI_LEN + 1), "")
           .accounts({
-            owner: client.publicKey,
-            task: task.publicKey,
+            owner: agent.publicKey,
+            profile: getProfilePda(agent.publicKey),
             systemProgram: SystemProgram.programId,
           })
-          .signers([client, task])
+          .signers([agent])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("EmptyTitle");
+        expect(error.toString()).to.include("UriTooLong");
       }
     });
 
-    it("should fail to post task with title exceeding max length", async () => {
-      const task = Keypair.generate();
-      const longTitle = "a".repeat(MAX_TITLE_LEN + 1);
-      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+    it("should refuse to migrate a profile already at the current version", async () => {
+      const agent = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        agent.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      const profilePda = await createProfile(agent, "Agent");
       
       try {
         await program.methods
-          .postTask(longTitle, "Description", new anchor.BN(1000000), milestones, deadline)
+          .migrateAgentProfile()
           .accounts({
-            owner: client.publicKey,
-            task: task.publicKey,
+            owner: agent.publicKey,
+            profile: profilePda,
             systemProgram: SystemProgram.programId,
           })
-          .signers([client, task])
+          .signers([agent])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("TitleTooLong");
+        expect(error.toString()).to.include("ProfileAlreadyMigrated");
       }
     });
 
-    it("should fail to post task with description exceeding max length", async () => {
-      const task = Keypair.generate();
-      const longDescription = "a".repeat(MAX_DESC_LEN + 1);
-      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+    it("should add and remove skill tags in the skill index", async () => {
+      const agent = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        agent.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      const profilePda = await createProfile(agent, "Auditor");
+      const tag = "rust-audit-" + agent.publicKey.toBase58().slice(0, 8).toLowerCase();
+      const skillIndexPda = getSkillIndexPda(tag);
+      
+      await program.methods
+        .addSkillTag(tag)
+        .accounts({
+          owner: agent.publicKey,
+          profile: profilePda,
+          skillIndex: skillIndexPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([agent])
+        .rpc();
+      
+      let profile = await program.account.agentProfile.fetch(profilePda);
+      expect(profile.skillTags).to.deep.equal([tag]);
+      let skillIndex: SkillIndex = await program.account.skillIndex.fetch(skillIndexPda);
+      expect(skillIndex.tag).to.equal(tag);
+      expect(skillIndex.profiles.map((p) => p.toBase58())).to.deep.equal([profilePda.toBase58()]);
       
+      // A closed profile would otherwise linger in the index
       try {
         await program.methods
-          .postTask("Title", longDescription, new anchor.BN(1000000), milestones, deadline)
+          .closeAgentProfile()
           .accounts({
-            owner: client.publicKey,
-            task: task.publicKey,
-            systemProgram: SystemProgram.programId,
+            owner: agent.publicKey,
+            profile: profilePda,
           })
-          .signers([client, task])
+          .signers([agent])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("DescriptionTooLong");
+        expect(error.toString()).to.include("ProfileHasSkillTags");
       }
-    });
-
-    it("should fail to post task with no milestones", async () => {
-      const task = Keypair.generate();
-      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       
-      try {
-        await program.methods
-          .postTask("Title", "Description", new anchor.BN(1000000), [], deadline)
-          .accounts({
-            owner: client.publicKey,
-            task: task.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([client, task])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("NoMilestones");
-      }
+      await program.methods
+        .removeSkillTag(tag)
+        .accounts({
+          owner: agent.publicKey,
+          profile: profilePda,
+          skillIndex: skillIndexPda,
+        })
+        .signers([agent])
+        .rpc();
+      
+      profile = await program.account.agentProfile.fetch(profilePda);
+      expect(profile.skillTags).to.be.empty;
+      skillIndex = await program.account.skillIndex.fetch(skillIndexPda);
+      expect(skillIndex.profiles).to.be.empty;
     });
 
-    it("should fail to post task with too many milestones", async () => {
-      const task = Keypair.generate();
-      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
-      const tooManyMilestones: Milestone[] = Array(11).fill(null).map((_, i) => ({
-        description: `Milestone ${i + 1}`,
-        amount: new anchor.BN(100000),
-        completed: false,
-        paid: false,
-      }));
+    it("should reject a skill tag with uppercase characters", async () => {
+      const agent = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        agent.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      const profilePda = await createProfile(agent, "Agent");
       
       try {
         await program.methods
-          .postTask("Title", "Description", new anchor.BN(1100000), tooManyMilestones, deadline)
+          .addSkillTag("Rust-Audit")
           .accounts({
-            owner: client.publicKey,
-            task: task.publicKey,
+            owner: agent.publicKey,
+            profile: profilePda,
+            skillIndex: getSkillIndexPda("Rust-Audit"),
             systemProgram: SystemProgram.programId,
           })
-          .signers([client, task])
+          .signers([agent])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("TooManyMilestones");
+        expect(error.toString()).to.include("InvalidSkillTag");
       }
     });
 
-    it("should fail to post task when milestone amounts don't sum to budget", async () => {
+    it("should only close a profile with no open tasks", async () => {
+      const agent = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        agent.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(agent, "Agent");
+      const profilePda = getProfilePda(agent.publicKey);
+      
       const task = Keypair.generate();
       const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Task",
+          "Description",
+          new anchor.BN(1000000),
+          [{ description: "All", amount: new anchor.BN(1000000), completed: false, paid: false, approvals: 0 }],
+          deadline,
+          null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: agent.publicKey,
+          task: task.publicKey,
+          ownerProfile: profilePda,
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([agent, task])
+        .rpc();
       
-      const badMilestones: Milestone[] = [
-        {
-          description: "Milestone 1",
-          amount: new anchor.BN(500000),
-          completed: false,
-          paid: false,
-        },
-        {
-          description: "Milestone 2",
-          amount: new anchor.BN(300000),
-          completed: false,
-          paid: false,
-        },
-      ]; // Sum is 800000, but budget is 1000000
+      let profile = await program.account.agentProfile.fetch(profilePda);
+      expect(profile.openTasks).to.equal(1);
       
       try {
         await program.methods
-          .postTask("Title", "Description", new anchor.BN(1000000), badMilestones, deadline)
+          .closeAgentProfile()
           .accounts({
-            owner: client.publicKey,
-            task: task.publicKey,
-            systemProgram: SystemProgram.programId,
+            owner: agent.publicKey,
+            profile: profilePda,
           })
-          .signers([client, task])
+          .signers([agent])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("MilestoneAmountMismatch");
+        expect(error.toString()).to.include("ProfileHasOpenWork");
       }
-    });
-
-    it("should fail to post task with deadline in the past", async () => {
-      const task = Keypair.generate();
-      const pastDeadline = new anchor.BN(Math.floor(Date.now() / 1000) - 1000);
       
-      try {
-        await program.methods
-          .postTask("Title", "Description", new anchor.BN(1000000), milestones, pastDeadline)
-          .accounts({
-            owner: client.publicKey,
-            task: task.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([client, task])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("InvalidDeadline");
-      }
+      await program.methods
+        .cancelTask()
+        .accounts({
+          owner: agent.publicKey,
+          task: task.publicKey,
+          ownerProfile: profilePda,
+          ...(await categoryAccountsFor(task.publicKey)),
+        })
+        .signers([agent])
+        .rpc();
+      
+      profile = await program.account.agentProfile.fetch(profilePda);
+      expect(profile.openTasks).to.equal(0);
+      
+      await program.methods
+        .closeAgentProfile()
+        .accounts({
+          owner: agent.publicKey,
+          profile: profilePda,
+        })
+        .signers([agent])
+        .rpc();
+      
+      const closed = await provider.connection.getAccountInfo(profilePda);
+      expect(closed).to.be.null;
     });
   });
 
   // ============================================================================
-  // BID SUBMISSION TESTS
+  // TASK POSTING TESTS
   // ============================================================================
   
-  describe("Bid Submission", () => {
+  describe("Task Posting", () => {
     let client: Keypair;
-    let freelancer: Keypair;
-    let taskKeypair: Keypair;
-    let taskPubkey: PublicKey;
     let milestones: Milestone[];
     
     beforeEach(async () => {
-      // Setup client
       client = Keypair.generate();
-      let sig = await provider.connection.requestAirdrop(
+      const sig = await provider.connection.requestAirdrop(
         client.publicKey,
         5 * anchor.web3.LAMPORTS_PER_SOL
       );
       await provider.connection.confirmTransaction(sig);
-      await sleep(500);
+      await sleep(500); // Delay to avoid rate limiting
       
-      // Setup freelancer
-      freelancer = Keypair.generate();
-      sig = await provider.connection.requestAirdrop(
-        freelancer.publicKey,
-        5 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(sig);
-      await sleep(500);
+      await createProfile(client, "Client");
       
-      // Create task
-      taskKeypair = Keypair.generate();
-      taskPubkey = taskKeypair.publicKey;
       milestones = [
         {
-          description: "Initial setup",
+          description: "Initial setup and planning",
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
+          approvals: 0,
         },
         {
-          description: "Development",
+          description: "Development phase",
           amount: new anchor.BN(2000000),
           completed: false,
           paid: false,
+          approvals: 0,
+        },
+        {
+          description: "Testing and delivery",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
         },
       ];
-      
-      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
-      await program.methods
-        .postTask("Test Task", "Test Description", new anchor.BN(3000000), milestones, deadline)
-        .accounts({
-          owner: client.publicKey,
-          task: taskPubkey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([client, taskKeypair])
-        .rpc();
     });
 
-    it("should submit a bid with valid parameters", async () => {
-      const bid = Keypair.generate();
-      const amount = new anchor.BN(2500000);
-      const timeline = new anchor.BN(86400 * 5); // 5 days
-      const proposal = "I can complete this task efficiently";
+    it("should post a task with valid parameters", async () => {
+      const task = Keypair.generate();
+      const title = "Build a Solana dApp";
+      const description = "Create a decentralized application on Solana blockchain with specific features";
+      const budget = new anchor.BN(4000000);
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7); // 7 days from now
       
       await program.methods
-        .submitBid(amount, timeline, proposal)
+        .postTask(
+          title, description, budget, milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
         .accounts({
-          bidder: freelancer.publicKey,
-          task: taskPubkey,
-          bid: bid.publicKey,
+          owner: client.publicKey,
+          task: task.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer, bid])
+        .signers([client, task])
         .rpc();
       
-      // Verify bid was created
-      const bidAccount = await program.account.bid.fetch(bid.publicKey);
-      expect(bidAccount.task.toBase58()).to.equal(taskPubkey.toBase58());
-      expect(bidAccount.bidder.toBase58()).to.equal(freelancer.publicKey.toBase58());
-      expect(bidAccount.amount.toNumber()).to.equal(amount.toNumber());
-      expect(bidAccount.timeline.toNumber()).to.equal(timeline.toNumber());
-      expect(bidAccount.proposal).to.equal(proposal);
-      expect(getBidStatus(bidAccount.status)).to.equal("Pending");
+      // Verify task was created
+      const taskAccount = await program.account.task.fetch(task.publicKey);
+      expect(taskAccount.owner.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(taskAccount.title).to.equal(title);
+      expect(taskAccount.description).to.equal(description);
+      expect(taskAccount.budget.toNumber()).to.equal(budget.toNumber());
+      expect(taskAccount.milestones.length).to.equal(3);
+      expect(taskAccount.deadline.toNumber()).to.equal(deadline.toNumber());
+      expect(getTaskStatus(taskAccount.status)).to.equal("Open");
+      expect(taskAccount.acceptedBid).to.be.null;
+      expect(taskAccount.escrowAccount).to.be.null;
+      expect(taskAccount.metadata).to.be.null;
+      
+      const clientProfile = await program.account.agentProfile.fetch(getProfilePda(client.publicKey));
+      expect(clientProfile.tasksPosted).to.equal(1);
     });
 
-    it("should fail when task owner tries to bid on own task", async () => {
-      const bid = Keypair.generate();
+    it("should fail to post task without an owner profile", async () => {
+      const stranger = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        stranger.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      const task = Keypair.generate();
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400);
       
       try {
         await program.methods
-          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal")
+          .postTask(
+            "Title", "Description", new anchor.BN(4000000), milestones, deadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
           .accounts({
-            bidder: client.publicKey,
-            task: taskPubkey,
-            bid: bid.publicKey,
+            owner: stranger.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(stranger.publicKey),
+            ...(await categoryPostAccounts()),
             systemProgram: SystemProgram.programId,
           })
-          .signers([client, bid])
+          .signers([stranger, task])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        // The constraint should fail
-        expect(error.toString()).to.include("constraint was violated");
+        expect(error.toString()).to.include("AccountNotInitialized");
       }
     });
 
-    it("should fail to submit bid with amount equal to zero", async () => {
-      const bid = Keypair.generate();
+    it("should fail to post task with empty title", async () => {
+      const task = Keypair.generate();
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       
       try {
         await program.methods
-          .submitBid(new anchor.BN(0), new anchor.BN(86400), "Proposal")
+          .postTask(
+            "", "Description", new anchor.BN(1000000), milestones, deadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
           .accounts({
-            bidder: freelancer.publicKey,
-            task: taskPubkey,
-            bid: bid.publicKey,
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryPostAccounts()),
             systemProgram: SystemProgram.programId,
           })
-          .signers([freelancer, bid])
+          .signers([client, task])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("InvalidAmount");
+        expect(error.toString()).to.include("EmptyTitle");
       }
     });
 
-    it("should fail to submit bid with timeline equal to zero", async () => {
-      const bid = Keypair.generate();
+    it("should fail to post task with title exceeding max length", async () => {
+      const task = Keypair.generate();
+      const longTitle = "a".repeat(MAX_TITLE_LEN + 1);
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       
       try {
         await program.methods
-          .submitBid(new anchor.BN(1000000), new anchor.BN(0), "Proposal")
+          .postTask(
+            longTitle, "Description", new anchor.BN(1000000), milestones, deadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
           .accounts({
-            bidder: freelancer.publicKey,
-            task: taskPubkey,
-            bid: bid.publicKey,
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryPostAccounts()),
             systemProgram: SystemProgram.programId,
           })
-          .signers([freelancer, bid])
+          .signers([client, task])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("InvalidTimeline");
+        expect(error.toString()).to.include("TitleTooLong");
       }
     });
 
-    it("should fail to submit bid when timeline exceeds task deadline", async () => {
-      const bid = Keypair.generate();
-      const longTimeline = new anchor.BN(86400 * 10); // 10 days, but task deadline is 7 days
-      
-      try {
+    it("should fail to post task with description exceeding max length", async () => {
+      const task = Keypair.generate();
+      const longDescription = "a".repeat(MAX_DESC_LEN + 1);
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      
+      try {
         await program.methods
-          .submitBid(new anchor.BN(1000000), longTimeline, "Proposal")
+          .postTask(
+            "Title", longDescription, new anchor.BN(1000000), milestones, deadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
           .accounts({
-            bidder: freelancer.publicKey,
-            task: taskPubkey,
-            bid: bid.publicKey,
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryPostAccounts()),
             systemProgram: SystemProgram.programId,
           })
-          .signers([freelancer, bid])
+          .signers([client, task])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("TimelineExceedsDeadline");
+        expect(error.toString()).to.include("DescriptionTooLong");
       }
     });
 
-    it("should fail to submit bid with proposal exceeding max length", async () => {
-      const bid = Keypair.generate();
-      const longProposal = "a".repeat(MAX_PROPOSAL_LEN + 1);
+    it("should fail to post task with no milestones", async () => {
+      const task = Keypair.generate();
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       
       try {
         await program.methods
-          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), longProposal)
+          .postTask(
+            "Title", "Description", new anchor.BN(1000000), [], deadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
           .accounts({
-            bidder: freelancer.publicKey,
-            task: taskPubkey,
-            bid: bid.publicKey,
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryPostAccounts()),
             systemProgram: SystemProgram.programId,
           })
-          .signers([freelancer, bid])
+          .signers([client, task])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("ProposalTooLong");
+        expect(error.toString()).to.include("NoMilestones");
       }
     });
 
-    it("should fail to submit bid on non-open task", async () => {
-      // First accept a bid to make task InProgress
-      const bid1 = Keypair.generate();
+    it("should fail to post task with too many milestones", async () => {
+      const task = Keypair.generate();
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      const tooManyMilestones: Milestone[] = Array(11).fill(null).map((_, i) => ({
+        description: `Milestone ${i + 1}`,
+        amount: new anchor.BN(100000),
+        completed: false,
+        paid: false,
+        approvals: 0,
+      }));
+      
+      try {
+        await program.methods
+          .postTask(
+            "Title", "Description", new anchor.BN(1100000), tooManyMilestones, deadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
+          .accounts({
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryPostAccounts()),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client, task])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("TooManyMilestones");
+      }
+    });
+
+    it("should fail to post task when milestone amounts don't sum to budget", async () => {
+      const task = Keypair.generate();
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      
+      const badMilestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(500000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+        {
+          description: "Milestone 2",
+          amount: new anchor.BN(300000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ]; // Sum is 800000, but budget is 1000000
+      
+      try {
+        await program.methods
+          .postTask(
+            "Title", "Description", new anchor.BN(1000000), badMilestones, deadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
+          .accounts({
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryPostAccounts()),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client, task])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MilestoneAmountMismatch");
+      }
+    });
+
+    it("should fail to post task with deadline in the past", async () => {
+      const task = Keypair.generate();
+      const pastDeadline = new anchor.BN(Math.floor(Date.now() / 1000) - 1000);
+      
+      try {
+        await program.methods
+          .postTask(
+            "Title", "Description", new anchor.BN(1000000), milestones, pastDeadline, null,
+            { development: {} },
+            await currentCategoryPage(),
+            null
+          )
+          .accounts({
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryPostAccounts()),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client, task])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidDeadline");
+      }
+    });
+
+    it("should post a compact task with an off-chain description", async () => {
+      const task = Keypair.generate();
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      const metadata: TaskMetadata = {
+        uri: "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+        contentHash: Array.from(createHash("sha256").update("v1").digest()),
+      };
+      
       await program.methods
-        .submitBid(new anchor.BN(2000000), new anchor.BN(86400), "First bid")
+        .postTask(
+          "Compact Task", "", new anchor.BN(4000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          metadata
+        )
         .accounts({
-          bidder: freelancer.publicKey,
-          task: taskPubkey,
-          bid: bid1.publicKey,
+          owner: client.publicKey,
+          task: task.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer, bid1])
+        .signers([client, task])
         .rpc();
       
+      let taskAccount = await program.account.task.fetch(task.publicKey);
+      expect(taskAccount.description).to.equal("");
+      expect(taskAccount.metadata).to.deep.equal(metadata);
+      
+      // No space is reserved for an inline description
+      const info = await provider.connection.getAccountInfo(task.publicKey);
+      expect(info!.data.length).to.be.lessThan(MAX_DESC_LEN);
+      
+      // Pointing at a new document refreshes the hash
+      const updated: TaskMetadata = {
+        uri: metadata.uri,
+        contentHash: Array.from(createHash("sha256").update("v2").digest()),
+      };
       await program.methods
-        .acceptBid()
+        .updateTask(null, null, null, updated, [])
         .accounts({
           owner: client.publicKey,
-          task: taskPubkey,
-          bid: bid1.publicKey,
+          task: task.publicKey,
+          systemProgram: SystemProgram.programId,
         })
         .signers([client])
         .rpc();
       
-      // Now try to submit another bid
-      const anotherFreelancer = Keypair.generate();
-      const sig = await provider.connection.requestAirdrop(
-        anotherFreelancer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(sig);
-      await sleep(500);
+      taskAccount = await program.account.task.fetch(task.publicKey);
+      expect(taskAccount.metadata).to.deep.equal(updated);
       
-      const bid2 = Keypair.generate();
       try {
         await program.methods
-          .submitBid(new anchor.BN(1500000), new anchor.BN(43200), "Second bid")
+          .updateTask("Inline description", null, null, null, [])
           .accounts({
-            bidder: anotherFreelancer.publicKey,
-            task: taskPubkey,
-            bid: bid2.publicKey,
+            owner: client.publicKey,
+            task: task.publicKey,
             systemProgram: SystemProgram.programId,
           })
-          .signers([anotherFreelancer, bid2])
+          .signers([client])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("constraint was violated");
+        expect(error.toString()).to.include("InlineDescriptionOnCompactTask");
+      }
+    });
+
+    it("should list open tasks in their category index until cancelled", async () => {
+      const task = Keypair.generate();
+      const category: TaskCategory = { dataLabeling: {} };
+      const page = await currentCategoryPage(category);
+      const categoryHead = getCategoryHeadPda(category);
+      const categoryIndex = getCategoryIndexPda(category, page);
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      
+      // Pages are handed out in order, so a task can't open a page of its own
+      try {
+        await program.methods
+          .postTask(
+            "Label images", "Description", new anchor.BN(4000000), milestones, deadline, null,
+            category,
+            page + 1,
+            null
+          )
+          .accounts({
+            owner: client.publicKey,
+            task: task.publicKey,
+            ownerProfile: getProfilePda(client.publicKey),
+            categoryHead,
+            categoryIndex: getCategoryIndexPda(category, page + 1),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client, task])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("StaleCategoryPage");
       }
+      
+      await program.methods
+        .postTask(
+          "Label images", "Description", new anchor.BN(4000000), milestones, deadline, null,
+          category,
+          page,
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: task.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          categoryHead,
+          categoryIndex,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(task.publicKey);
+      expect(taskAccount.category).to.deep.equal(category);
+      expect(taskAccount.categoryPage).to.equal(page);
+      
+      const head = await program.account.categoryHead.fetchNullable(categoryHead);
+      expect(head!.category).to.deep.equal(category);
+      expect(head!.currentPage).to.equal(page);
+      
+      let index = await program.account.categoryIndex.fetch(categoryIndex);
+      expect(index.category).to.deep.equal(category);
+      expect(index.page).to.equal(page);
+      expect(index.tasks.map((k) => k.toBase58())).to.include(task.publicKey.toBase58());
+      
+      await program.methods
+        .cancelTask()
+        .accounts({
+          owner: client.publicKey,
+          task: task.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          categoryIndex,
+          categoryHead,
+        })
+        .signers([client])
+        .rpc();
+      
+      index = await program.account.categoryIndex.fetch(categoryIndex);
+      expect(index.tasks.map((k) => k.toBase58())).to.not.include(task.publicKey.toBase58());
     });
   });
 
   // ============================================================================
-  // BID ACCEPTANCE TESTS
+  // BID SUBMISSION TESTS
   // ============================================================================
   
-  describe("Bid Acceptance", () => {
+  describe("Bid Submission", () => {
     let client: Keypair;
     let freelancer: Keypair;
     let taskKeypair: Keypair;
     let taskPubkey: PublicKey;
-    let bidKeypair: Keypair;
-    let bidPubkey: PublicKey;
     let milestones: Milestone[];
     
     beforeEach(async () => {
+      // Setup client
       client = Keypair.generate();
-      freelancer = Keypair.generate();
-      
       let sig = await provider.connection.requestAirdrop(
         client.publicKey,
         5 * anchor.web3.LAMPORTS_PER_SOL
@@ -818,6 +1601,8 @@ describe("Agora - Agent Task Marketplace", () => {
       await provider.connection.confirmTransaction(sig);
       await sleep(500);
       
+      // Setup freelancer
+      freelancer = Keypair.generate();
       sig = await provider.connection.requestAirdrop(
         freelancer.publicKey,
         5 * anchor.web3.LAMPORTS_PER_SOL
@@ -825,111 +1610,377 @@ describe("Agora - Agent Task Marketplace", () => {
       await provider.connection.confirmTransaction(sig);
       await sleep(500);
       
+      // Create task
+      await createProfile(client, "Client");
+      
       taskKeypair = Keypair.generate();
       taskPubkey = taskKeypair.publicKey;
       milestones = [
         {
-          description: "Milestone 1",
+          description: "Initial setup",
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
+          approvals: 0,
+        },
+        {
+          description: "Development",
+          amount: new anchor.BN(2000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
         },
       ];
       
       const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       await program.methods
-        .postTask("Task", "Description", new anchor.BN(1000000), milestones, deadline)
+        .postTask(
+          "Test Task", "Test Description", new anchor.BN(3000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
         .signers([client, taskKeypair])
         .rpc();
-      await sleep(500);
+    });
+
+    it("should submit a bid with valid parameters", async () => {
+      const bid = Keypair.generate();
+      const amount = new anchor.BN(2500000);
+      const timeline = new anchor.BN(86400 * 5); // 5 days
+      const proposal = "I can complete this task efficiently";
       
-      bidKeypair = Keypair.generate();
-      bidPubkey = bidKeypair.publicKey;
       await program.methods
-        .submitBid(new anchor.BN(900000), new anchor.BN(86400 * 3), "My proposal")
+        .submitBid(amount, timeline, proposal, [])
         .accounts({
           bidder: freelancer.publicKey,
           task: taskPubkey,
-          bid: bidPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
           systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer, bidKeypair])
+        .signers([freelancer, bid])
         .rpc();
+      
+      // Verify bid was created
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      expect(bidAccount.task.toBase58()).to.equal(taskPubkey.toBase58());
+      expect(bidAccount.bidder.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(bidAccount.amount.toNumber()).to.equal(amount.toNumber());
+      expect(bidAccount.timeline.toNumber()).to.equal(timeline.toNumber());
+      expect(bidAccount.proposal).to.equal(proposal);
+      expect(getBidStatus(bidAccount.status)).to.equal("Pending");
     });
 
-    it("should accept a pending bid by task owner", async () => {
+    it("should scale the task's milestone schedule when bid has none", async () => {
+      const bid = Keypair.generate();
+      
       await program.methods
-        .acceptBid()
+        .submitBid(new anchor.BN(1500000), new anchor.BN(86400), "Half price", [])
         .accounts({
-          owner: client.publicKey,
+          bidder: freelancer.publicKey,
           task: taskPubkey,
-          bid: bidPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
         })
-        .signers([client])
+        .signers([freelancer, bid])
         .rpc();
       
-      // Verify bid status changed
-      const bidAccount = await program.account.bid.fetch(bidPubkey);
-      expect(getBidStatus(bidAccount.status)).to.equal("Accepted");
-      
-      // Verify task updated
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(getTaskStatus(taskAccount.status)).to.equal("InProgress");
-      expect(taskAccount.acceptedBid?.toBase58()).to.equal(bidPubkey.toBase58());
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      expect(bidAccount.milestoneAmounts.map((a) => a.toNumber())).to.deep.equal([500000, 1000000]);
     });
 
-    it("should fail when non-owner tries to accept bid", async () => {
-      const attacker = Keypair.generate();
-      const sig = await provider.connection.requestAirdrop(
-        attacker.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(sig);
-      await sleep(500);
+    it("should submit a bid with its own milestone schedule", async () => {
+      const bid = Keypair.generate();
+      const schedule = [new anchor.BN(1500000), new anchor.BN(1000000)];
+      
+      await program.methods
+        .submitBid(new anchor.BN(2500000), new anchor.BN(86400), "Front-loaded", schedule)
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      expect(bidAccount.milestoneAmounts.map((a) => a.toNumber())).to.deep.equal([1500000, 1000000]);
+    });
+
+    it("should fail when bid milestone amounts don't sum to bid amount", async () => {
+      const bid = Keypair.generate();
       
       try {
         await program.methods
-          .acceptBid()
+          .submitBid(
+            new anchor.BN(2500000),
+            new anchor.BN(86400),
+            "Proposal",
+            [new anchor.BN(1000000), new anchor.BN(1000000)]
+          )
           .accounts({
-            owner: attacker.publicKey,
+            bidder: freelancer.publicKey,
             task: taskPubkey,
-            bid: bidPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
           })
-          .signers([attacker])
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MilestoneAmountMismatch");
+      }
+    });
+
+    it("should fail when bid schedule doesn't match task milestone count", async () => {
+      const bid = Keypair.generate();
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(2500000), new anchor.BN(86400), "Proposal", [new anchor.BN(2500000)])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidMilestoneMismatch");
+      }
+    });
+
+    it("should fail when task owner tries to bid on own task", async () => {
+      const bid = Keypair.generate();
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: client.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client, bid])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
+        // The constraint should fail
         expect(error.toString()).to.include("constraint was violated");
       }
     });
 
-    it("should fail to accept already accepted bid", async () => {
-      // First accept the bid
+    it("should fail to submit bid with amount equal to zero", async () => {
+      const bid = Keypair.generate();
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(0), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidAmount");
+      }
+    });
+
+    it("should fail to submit bid with timeline equal to zero", async () => {
+      const bid = Keypair.generate();
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(0), "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidTimeline");
+      }
+    });
+
+    it("should fail to submit bid when timeline exceeds task deadline", async () => {
+      const bid = Keypair.generate();
+      const longTimeline = new anchor.BN(86400 * 10); // 10 days, but task deadline is 7 days
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), longTimeline, "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("TimelineExceedsDeadline");
+      }
+    });
+
+    it("should fail to submit bid with proposal exceeding max length", async () => {
+      const bid = Keypair.generate();
+      const longProposal = "a".repeat(MAX_PROPOSAL_LEN + 1);
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), longProposal, [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("ProposalTooLong");
+      }
+    });
+
+    it("should fail to submit bid on non-open task", async () => {
+      // First accept a bid to make task InProgress
+      const bid1 = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(2000000), new anchor.BN(86400), "First bid", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid1.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid1])
+        .rpc();
+      
       await program.methods
         .acceptBid()
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
-          bid: bidPubkey,
+          bid: bid1.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
         })
         .signers([client])
         .rpc();
       
-      // Try to accept again
+      // Now try to submit another bid
+      const anotherFreelancer = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        anotherFreelancer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      const bid2 = Keypair.generate();
       try {
         await program.methods
-          .acceptBid()
+          .submitBid(new anchor.BN(1500000), new anchor.BN(43200), "Second bid", [])
           .accounts({
-            owner: client.publicKey,
+            bidder: anotherFreelancer.publicKey,
             task: taskPubkey,
-            bid: bidPubkey,
+            bid: bid2.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
           })
-          .signers([client])
+          .signers([anotherFreelancer, bid2])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
@@ -939,10 +1990,10 @@ describe("Agora - Agent Task Marketplace", () => {
   });
 
   // ============================================================================
-  // BID REJECTION AND WITHDRAWAL TESTS
+  // BID ACCEPTANCE TESTS
   // ============================================================================
   
-  describe("Bid Rejection and Withdrawal", () => {
+  describe("Bid Acceptance", () => {
     let client: Keypair;
     let freelancer: Keypair;
     let taskKeypair: Keypair;
@@ -969,6 +2020,8 @@ describe("Agora - Agent Task Marketplace", () => {
       await provider.connection.confirmTransaction(sig);
       await sleep(500);
       
+      await createProfile(client, "Client");
+      
       taskKeypair = Keypair.generate();
       taskPubkey = taskKeypair.publicKey;
       milestones = [
@@ -977,87 +2030,95 @@ describe("Agora - Agent Task Marketplace", () => {
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
+          approvals: 0,
         },
       ];
       
       const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       await program.methods
-        .postTask("Task", "Description", new anchor.BN(1000000), milestones, deadline)
+        .postTask(
+          "Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
         .signers([client, taskKeypair])
         .rpc();
+      await sleep(500);
       
       bidKeypair = Keypair.generate();
       bidPubkey = bidKeypair.publicKey;
       await program.methods
-        .submitBid(new anchor.BN(900000), new anchor.BN(86400 * 3), "My proposal")
+        .submitBid(new anchor.BN(900000), new anchor.BN(86400 * 3), "My proposal", [])
         .accounts({
           bidder: freelancer.publicKey,
           task: taskPubkey,
           bid: bidPubkey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
           systemProgram: SystemProgram.programId,
         })
         .signers([freelancer, bidKeypair])
         .rpc();
     });
 
-    it("should reject a pending bid by task owner", async () => {
+    it("should accept a pending bid by task owner", async () => {
       await program.methods
-        .rejectBid()
+        .acceptBid()
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
           bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
         })
         .signers([client])
         .rpc();
       
+      // Verify bid status changed
       const bidAccount = await program.account.bid.fetch(bidPubkey);
-      expect(getBidStatus(bidAccount.status)).to.equal("Rejected");
-    });
-
-    it("should fail when non-owner tries to reject bid", async () => {
-      try {
-        await program.methods
-          .rejectBid()
-          .accounts({
-            owner: freelancer.publicKey,
-            task: taskPubkey,
-            bid: bidPubkey,
-          })
-          .signers([freelancer])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("constraint was violated");
-      }
+      expect(getBidStatus(bidAccount.status)).to.equal("Accepted");
+      
+      // Verify task updated
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(getTaskStatus(taskAccount.status)).to.equal("InProgress");
+      expect(taskAccount.acceptedBid?.toBase58()).to.equal(bidPubkey.toBase58());
+      
+      // Accepted tasks are no longer listed as open
+      const index = await program.account.categoryIndex.fetch(await categoryIndexFor(taskPubkey));
+      expect(index.tasks.map((k) => k.toBase58())).to.not.include(taskPubkey.toBase58());
     });
 
-    it("should withdraw a pending bid by bidder", async () => {
+    it("should rewrite the task's milestone schedule to match the accepted bid", async () => {
       await program.methods
-        .withdrawBid()
+        .acceptBid()
         .accounts({
-          bidder: freelancer.publicKey,
+          owner: client.publicKey,
+          task: taskPubkey,
           bid: bidPubkey,
-          systemProgram: SystemProgram.programId,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
         })
-        .signers([freelancer])
+        .signers([client])
         .rpc();
       
-      // Bid account should be closed
-      try {
-        await program.account.bid.fetch(bidPubkey);
-        expect.fail("Bid should have been closed");
-      } catch (error: any) {
-        expect(error.toString()).to.include("Account does not exist");
-      }
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.budget.toNumber()).to.equal(900000);
+      expect(taskAccount.milestones[0].amount.toNumber()).to.equal(900000);
     });
 
-    it("should fail when non-bidder tries to withdraw bid", async () => {
+    it("should fail when non-owner tries to accept bid", async () => {
       const attacker = Keypair.generate();
       const sig = await provider.connection.requestAirdrop(
         attacker.publicKey,
@@ -1068,11 +2129,13 @@ describe("Agora - Agent Task Marketplace", () => {
       
       try {
         await program.methods
-          .withdrawBid()
+          .acceptBid()
           .accounts({
-            bidder: attacker.publicKey,
+            owner: attacker.publicKey,
+            task: taskPubkey,
             bid: bidPubkey,
-            systemProgram: SystemProgram.programId,
+            bidderStake: null,
+            ...(await categoryAccountsFor(taskPubkey)),
           })
           .signers([attacker])
           .rpc();
@@ -1081,27 +2144,74 @@ describe("Agora - Agent Task Marketplace", () => {
         expect(error.toString()).to.include("constraint was violated");
       }
     });
-  });
 
-  // ============================================================================
-  // TASK UPDATE TESTS
-  // ============================================================================
-  
-  describe("Task Update", () => {
-    let client: Keypair;
-    let taskKeypair: Keypair;
-    let taskPubkey: PublicKey;
-    let milestones: Milestone[];
-    
+    it("should fail to accept already accepted bid", async () => {
+      // First accept the bid
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      // Try to accept again
+      try {
+        await program.methods
+          .acceptBid()
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidderStake: null,
+            ...(await categoryAccountsFor(taskPubkey)),
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
+      }
+    });
+  });
+
+  // ============================================================================
+  // BID REJECTION AND WITHDRAWAL TESTS
+  // ============================================================================
+  
+  describe("Bid Rejection and Withdrawal", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    let bidKeypair: Keypair;
+    let bidPubkey: PublicKey;
+    let milestones: Milestone[];
+    
     beforeEach(async () => {
       client = Keypair.generate();
-      const sig = await provider.connection.requestAirdrop(
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
         client.publicKey,
         5 * anchor.web3.LAMPORTS_PER_SOL
       );
       await provider.connection.confirmTransaction(sig);
       await sleep(500);
       
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      
       taskKeypair = Keypair.generate();
       taskPubkey = taskKeypair.publicKey;
       milestones = [
@@ -1110,86 +2220,73 @@ describe("Agora - Agent Task Marketplace", () => {
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
+          approvals: 0,
         },
       ];
       
       const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       await program.methods
-        .postTask("Original Title", "Original Description", new anchor.BN(1000000), milestones, deadline)
+        .postTask(
+          "Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
         .signers([client, taskKeypair])
         .rpc();
-    });
-
-    it("should update task description", async () => {
-      const newDescription = "Updated Description";
-      
-      await program.methods
-        .updateTask(newDescription, null, null)
-        .accounts({
-          owner: client.publicKey,
-          task: taskPubkey,
-        })
-        .signers([client])
-        .rpc();
-      
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(taskAccount.description).to.equal(newDescription);
-    });
-
-    it("should update task budget", async () => {
-      const newBudget = new anchor.BN(2000000);
       
+      bidKeypair = Keypair.generate();
+      bidPubkey = bidKeypair.publicKey;
       await program.methods
-        .updateTask(null, newBudget, null)
+        .submitBid(new anchor.BN(900000), new anchor.BN(86400 * 3), "My proposal", [])
         .accounts({
-          owner: client.publicKey,
+          bidder: freelancer.publicKey,
           task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
         })
-        .signers([client])
+        .signers([freelancer, bidKeypair])
         .rpc();
-      
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(taskAccount.budget.toNumber()).to.equal(newBudget.toNumber());
     });
 
-    it("should update task deadline", async () => {
-      const newDeadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 14);
-      
+    it("should reject a pending bid by task owner", async () => {
       await program.methods
-        .updateTask(null, null, newDeadline)
+        .rejectBid()
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
+          bid: bidPubkey,
         })
         .signers([client])
         .rpc();
       
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(taskAccount.deadline.toNumber()).to.equal(newDeadline.toNumber());
+      const bidAccount = await program.account.bid.fetch(bidPubkey);
+      expect(getBidStatus(bidAccount.status)).to.equal("Rejected");
     });
 
-    it("should fail to update task when not owner", async () => {
-      const attacker = Keypair.generate();
-      const sig = await provider.connection.requestAirdrop(
-        attacker.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(sig);
-      await sleep(500);
-      
+    it("should fail when non-owner tries to reject bid", async () => {
       try {
         await program.methods
-          .updateTask("Hacked", null, null)
+          .rejectBid()
           .accounts({
-            owner: attacker.publicKey,
+            owner: freelancer.publicKey,
             task: taskPubkey,
+            bid: bidPubkey,
           })
-          .signers([attacker])
+          .signers([freelancer])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
@@ -1197,46 +2294,46 @@ describe("Agora - Agent Task Marketplace", () => {
       }
     });
 
-    it("should fail to update non-open task", async () => {
-      // First accept a bid to make task InProgress
-      const freelancer = Keypair.generate();
-      let sig = await provider.connection.requestAirdrop(
-        freelancer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(sig);
-      
-      const bid = Keypair.generate();
+    it("should withdraw a pending bid by bidder", async () => {
       await program.methods
-        .submitBid(new anchor.BN(900000), new anchor.BN(86400), "Proposal")
+        .withdrawBid()
         .accounts({
           bidder: freelancer.publicKey,
-          task: taskPubkey,
-          bid: bid.publicKey,
+          bid: bidPubkey,
+          bidBond: null,
           systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer, bid])
+        .signers([freelancer])
         .rpc();
       
-      await program.methods
-        .acceptBid()
-        .accounts({
-          owner: client.publicKey,
-          task: taskPubkey,
-          bid: bid.publicKey,
-        })
-        .signers([client])
-        .rpc();
+      // Bid account should be closed
+      try {
+        await program.account.bid.fetch(bidPubkey);
+        expect.fail("Bid should have been closed");
+      } catch (error: any) {
+        expect(error.toString()).to.include("Account does not exist");
+      }
+    });
+
+    it("should fail when non-bidder tries to withdraw bid", async () => {
+      const attacker = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        attacker.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
       
-      // Try to update
       try {
         await program.methods
-          .updateTask("Updated", null, null)
+          .withdrawBid()
           .accounts({
-            owner: client.publicKey,
-            task: taskPubkey,
+            bidder: attacker.publicKey,
+            bid: bidPubkey,
+            bidBond: null,
+            systemProgram: SystemProgram.programId,
           })
-          .signers([client])
+          .signers([attacker])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
@@ -1246,15 +2343,16 @@ describe("Agora - Agent Task Marketplace", () => {
   });
 
   // ============================================================================
-  // TASK CANCELLATION TESTS
+  // COUNTER-OFFER TESTS
   // ============================================================================
   
-  describe("Task Cancellation", () => {
+  describe("Counter Offers", () => {
     let client: Keypair;
     let freelancer: Keypair;
     let taskKeypair: Keypair;
     let taskPubkey: PublicKey;
-    let milestones: Milestone[];
+    let bidKeypair: Keypair;
+    let bidPubkey: PublicKey;
     
     beforeEach(async () => {
       client = Keypair.generate();
@@ -1274,104 +2372,220 @@ describe("Agora - Agent Task Marketplace", () => {
       await provider.connection.confirmTransaction(sig);
       await sleep(500);
       
+      await createProfile(client, "Client");
+      
       taskKeypair = Keypair.generate();
       taskPubkey = taskKeypair.publicKey;
-      milestones = [
+      const milestones: Milestone[] = [
         {
           description: "Milestone 1",
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
+          approvals: 0,
+        },
+        {
+          description: "Milestone 2",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
         },
       ];
       
       const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       await program.methods
-        .postTask("Task", "Description", new anchor.BN(1000000), milestones, deadline)
+        .postTask(
+          "Task", "Description", new anchor.BN(2000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
         .signers([client, taskKeypair])
         .rpc();
+      
+      bidKeypair = Keypair.generate();
+      bidPubkey = bidKeypair.publicKey;
+      await program.methods
+        .submitBid(new anchor.BN(2000000), new anchor.BN(86400 * 3), "My proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bidKeypair])
+        .rpc();
     });
 
-    it("should cancel an open task without escrow", async () => {
+    it("should record a counter-offer from the client", async () => {
       await program.methods
-        .cancelTask()
+        .counterOffer(new anchor.BN(1600000), new anchor.BN(86400 * 5))
         .accounts({
-          owner: client.publicKey,
+          party: client.publicKey,
           task: taskPubkey,
+          bid: bidPubkey,
         })
         .signers([client])
         .rpc();
       
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(getTaskStatus(taskAccount.status)).to.equal("Cancelled");
-    });
-
-    it("should fail to cancel task when not owner", async () => {
-      try {
-        await program.methods
-          .cancelTask()
-          .accounts({
-            owner: freelancer.publicKey,
-            task: taskPubkey,
-          })
-          .signers([freelancer])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("constraint was violated");
-      }
+      const bidAccount = await program.account.bid.fetch(bidPubkey);
+      expect(bidAccount.offers.length).to.equal(1);
+      expect(bidAccount.offers[0].amount.toNumber()).to.equal(1600000);
+      expect(bidAccount.offers[0].timeline.toNumber()).to.equal(86400 * 5);
+      expect(bidAccount.offers[0].proposedBy.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(getBidStatus(bidAccount.status)).to.equal("Pending");
     });
 
-    it("should fail to cancel task with escrow", async () => {
-      // Accept bid and fund escrow first
-      const bid = Keypair.generate();
+    it("should accept the client's counter-offer by the bidder", async () => {
       await program.methods
-        .submitBid(new anchor.BN(900000), new anchor.BN(86400), "Proposal")
+        .counterOffer(new anchor.BN(1600000), new anchor.BN(86400 * 5))
         .accounts({
-          bidder: freelancer.publicKey,
+          party: client.publicKey,
           task: taskPubkey,
-          bid: bid.publicKey,
-          systemProgram: SystemProgram.programId,
+          bid: bidPubkey,
         })
-        .signers([freelancer, bid])
+        .signers([client])
         .rpc();
       
       await program.methods
-        .acceptBid()
+        .acceptCounter()
         .accounts({
-          owner: client.publicKey,
+          party: freelancer.publicKey,
           task: taskPubkey,
-          bid: bid.publicKey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
         })
-        .signers([client])
+        .signers([freelancer])
         .rpc();
       
-      // Create token mint and fund escrow (simplified - in real test would need token setup)
-      // For this test, we verify the constraint works by checking escrow_account
-      // The escrow test below covers the full flow
+      const bidAccount = await program.account.bid.fetch(bidPubkey);
+      expect(getBidStatus(bidAccount.status)).to.equal("Accepted");
+      expect(bidAccount.amount.toNumber()).to.equal(1600000);
+      expect(bidAccount.timeline.toNumber()).to.equal(86400 * 5);
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(getTaskStatus(taskAccount.status)).to.equal("InProgress");
+      expect(taskAccount.budget.toNumber()).to.equal(1600000);
+      expect(taskAccount.milestones.map((m) => m.amount.toNumber())).to.deep.equal([800000, 800000]);
     });
-  });
 
-  // ============================================================================
-  // ESCROW AND TOKEN TESTS
-  // ============================================================================
-  
-  describe("Escrow and Payment Flow", () => {
-    let client: Keypair;
-    let freelancer: Keypair;
-    let taskKeypair: Keypair;
-    let taskPubkey: PublicKey;
-    let bidKeypair: Keypair;
-    let bidPubkey: PublicKey;
-    let tokenMint: PublicKey;
-    let clientTokenAccount: PublicKey;
-    let freelancerTokenAccount: PublicKey;
-    let milestones: Milestone[];
+    it("should fail when a party accepts their own offer", async () => {
+      await program.methods
+        .counterOffer(new anchor.BN(1600000), new anchor.BN(86400 * 5))
+        .accounts({
+          party: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+        })
+        .signers([client])
+        .rpc();
+      
+      try {
+        await program.methods
+          .acceptCounter()
+          .accounts({
+            party: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidderStake: null,
+            ...(await categoryAccountsFor(taskPubkey)),
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("CannotAcceptOwnOffer");
+      }
+    });
+
+    it("should fail to accept when no counter-offer exists", async () => {
+      try {
+        await program.methods
+          .acceptCounter()
+          .accounts({
+            party: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidderStake: null,
+            ...(await categoryAccountsFor(taskPubkey)),
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NoCounterOffer");
+      }
+    });
+
+    it("should fail when an outsider makes a counter-offer", async () => {
+      const outsider = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        outsider.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      try {
+        await program.methods
+          .counterOffer(new anchor.BN(100), new anchor.BN(86400))
+          .accounts({
+            party: outsider.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+          })
+          .signers([outsider])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NotBidParty");
+      }
+    });
+  });
+
+  // ============================================================================
+  // INVITE-ONLY TASK TESTS
+  // ============================================================================
+  
+  describe("Invite-Only Tasks", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    
+    const submitInvitedBid = async (bid: Keypair) => {
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Vetted proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+    };
     
     beforeEach(async () => {
       client = Keypair.generate();
@@ -1379,234 +2593,4136 @@ describe("Agora - Agent Task Marketplace", () => {
       
       let sig = await provider.connection.requestAirdrop(
         client.publicKey,
-        10 * anchor.web3.LAMPORTS_PER_SOL
+        5 * anchor.web3.LAMPORTS_PER_SOL
       );
       await provider.connection.confirmTransaction(sig);
       await sleep(500);
       
       sig = await provider.connection.requestAirdrop(
         freelancer.publicKey,
-        10 * anchor.web3.LAMPORTS_PER_SOL
+        5 * anchor.web3.LAMPORTS_PER_SOL
       );
       await provider.connection.confirmTransaction(sig);
       await sleep(500);
       
-      // Create token mint
-      tokenMint = await createMint(
-        provider.connection,
-        client,
-        client.publicKey,
-        null,
-        6
-      );
-      
-      // Create token accounts
-      clientTokenAccount = await createAccount(
-        provider.connection,
-        client,
-        tokenMint,
-        client.publicKey
-      );
-      
-      freelancerTokenAccount = await createAccount(
-        provider.connection,
-        freelancer,
-        tokenMint,
-        freelancer.publicKey
-      );
-      
-      // Mint tokens to client
-      await mintTo(
-        provider.connection,
-        client,
-        tokenMint,
-        clientTokenAccount,
-        client.publicKey,
-        10000000
-      );
+      await createProfile(client, "Client");
       
       taskKeypair = Keypair.generate();
       taskPubkey = taskKeypair.publicKey;
-      milestones = [
+      const milestones: Milestone[] = [
         {
           description: "Milestone 1",
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
-        },
-        {
-          description: "Milestone 2",
-          amount: new anchor.BN(2000000),
-          completed: false,
-          paid: false,
+          approvals: 0,
         },
       ];
       
       const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       await program.methods
-        .postTask("Task", "Description", new anchor.BN(3000000), milestones, deadline)
+        .postTask("Confidential Task", "Description", new anchor.BN(1000000), milestones, deadline, {
+          bidBond: new anchor.BN(0),
+          visibility: { inviteOnly: {} },
+          minTasksCompleted: 0,
+          minAverageRating: 0,
+          requiredSkills: [],
+          minSkillEndorsements: 0,
+          minStake: new anchor.BN(0),
+        }, { development: {} }, await currentCategoryPage(),
+        null)
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
         .signers([client, taskKeypair])
         .rpc();
-      
-      bidKeypair = Keypair.generate();
-      bidPubkey = bidKeypair.publicKey;
+    });
+
+    it("should reject bids from agents who are not invited", async () => {
+      try {
+        await submitInvitedBid(Keypair.generate());
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NotInvited");
+      }
+    });
+
+    it("should accept bids from invited agents", async () => {
       await program.methods
-        .submitBid(new anchor.BN(3000000), new anchor.BN(86400 * 5), "My proposal")
+        .addInvitee(freelancer.publicKey)
         .accounts({
-          bidder: freelancer.publicKey,
+          owner: client.publicKey,
           task: taskPubkey,
-          bid: bidPubkey,
-          systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer, bidKeypair])
+        .signers([client])
         .rpc();
       
-      // Accept bid
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.invitees.map((k) => k.toBase58())).to.deep.equal([freelancer.publicKey.toBase58()]);
+      
+      const bid = Keypair.generate();
+      await submitInvitedBid(bid);
+      
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      expect(getBidStatus(bidAccount.status)).to.equal("Pending");
+    });
+
+    it("should reject bids after the invitee is removed", async () => {
       await program.methods
-        .acceptBid()
+        .addInvitee(freelancer.publicKey)
         .accounts({
           owner: client.publicKey,
           task: taskPubkey,
-          bid: bidPubkey,
         })
         .signers([client])
         .rpc();
-    });
-
-    it("should fund escrow with tokens", async () => {
-      // Derive escrow PDA
-      const [escrowPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), taskPubkey.toBuffer()],
-        program.programId
-      );
-      
-      // Derive escrow token account PDA
-      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
-        program.programId
-      );
       
       await program.methods
-        .fundEscrow()
+        .removeInvitee(freelancer.publicKey)
         .accounts({
-          client: client.publicKey,
+          owner: client.publicKey,
           task: taskPubkey,
-          acceptedBid: bidPubkey,
-          escrow: escrowPda,
-          clientTokenAccount: clientTokenAccount,
-          escrowTokenAccount: escrowTokenPda,
-          tokenMint: tokenMint,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-          rent: SYSVAR_RENT_PUBKEY,
         })
         .signers([client])
         .rpc();
       
-      // Verify escrow was created
-      const escrowAccount = await program.account.escrow.fetch(escrowPda);
-      expect(escrowAccount.task.toBase58()).to.equal(taskPubkey.toBase58());
-      expect(escrowAccount.client.toBase58()).to.equal(client.publicKey.toBase58());
-      expect(escrowAccount.freelancer.toBase58()).to.equal(freelancer.publicKey.toBase58());
-      expect(escrowAccount.totalAmount.toNumber()).to.equal(3000000);
-      expect(escrowAccount.releasedAmount.toNumber()).to.equal(0);
-      expect(escrowAccount.tokenMint.toBase58()).to.equal(tokenMint.toBase58());
-      
-      // Verify tokens were transferred
-      const escrowTokenBalance = await getAccount(provider.connection, escrowTokenPda);
-      expect(Number(escrowTokenBalance.amount)).to.equal(3000000);
-      
-      const clientBalance = await getAccount(provider.connection, clientTokenAccount);
-      expect(Number(clientBalance.amount)).to.equal(7000000); // Started with 10M, transferred 3M
+      try {
+        await submitInvitedBid(Keypair.generate());
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NotInvited");
+      }
     });
 
-    it("should complete a milestone", async () => {
+    it("should fail when non-owner adds an invitee", async () => {
+      try {
+        await program.methods
+          .addInvitee(freelancer.publicKey)
+          .accounts({
+            owner: freelancer.publicKey,
+            task: taskPubkey,
+          })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
+      }
+    });
+
+    it("should fail to add the same invitee twice", async () => {
       await program.methods
-        .completeMilestone(0)
+        .addInvitee(freelancer.publicKey)
         .accounts({
-          freelancer: freelancer.publicKey,
+          owner: client.publicKey,
           task: taskPubkey,
-          bid: bidPubkey,
         })
-        .signers([freelancer])
+        .signers([client])
         .rpc();
       
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(taskAccount.milestones[0].completed).to.be.true;
-      expect(taskAccount.milestones[1].completed).to.be.false;
+      try {
+        await program.methods
+          .addInvitee(freelancer.publicKey)
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("AlreadyInvited");
+      }
     });
+  });
 
-    it("should release payment for completed milestone", async () => {
-      // First fund escrow
-      const [escrowPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), taskPubkey.toBuffer()],
-        program.programId
-      );
-      
-      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
-        program.programId
-      );
-      
+  // ============================================================================
+  // BLOCKLIST TESTS
+  // ============================================================================
+  
+  describe("Blocklists", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskPubkey: PublicKey;
+    let blocklistPda: PublicKey;
+    
+    const submitBid = async (ownerBlocklist: PublicKey = blocklistPda) => {
+      const bid = Keypair.generate();
       await program.methods
-        .fundEscrow()
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
         .accounts({
-          client: client.publicKey,
+          bidder: freelancer.publicKey,
           task: taskPubkey,
-          acceptedBid: bidPubkey,
-          escrow: escrowPda,
-          clientTokenAccount: clientTokenAccount,
-          escrowTokenAccount: escrowTokenPda,
-          tokenMint: tokenMint,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist,
           systemProgram: SystemProgram.programId,
-          rent: SYSVAR_RENT_PUBKEY,
         })
-        .signers([client])
+        .signers([freelancer, bid])
+        .rpc();
+    };
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      
+      blocklistPda = getBlocklistPda(client.publicKey);
+      
+      const task = Keypair.generate();
+      taskPubkey = task.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+      
+      await program.methods
+        .blockBidder(freelancer.publicKey)
+        .accounts({
+          owner: client.publicKey,
+          blocklist: blocklistPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+    });
+
+    it("should reject bids from blocked agents", async () => {
+      const blocklist = await program.account.blocklist.fetch(blocklistPda);
+      expect(blocklist.owner.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(blocklist.blocked.map((k) => k.toBase58())).to.include(freelancer.publicKey.toBase58());
+      
+      try {
+        await submitBid();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidderBlocked");
+      }
+    });
+
+    it("should not let a blocked agent skip the blocklist", async () => {
+      // Left out, the client derives the owner's blocklist from the task
+      const bid = Keypair.generate();
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidderBlocked");
+      }
+      
+      // An empty account in its place fails the seeds check
+      try {
+        await submitBid(getBlocklistPda(freelancer.publicKey));
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("ConstraintSeeds");
+      }
+    });
+
+    it("should reject blocking the same agent twice", async () => {
+      try {
+        await program.methods
+          .blockBidder(freelancer.publicKey)
+          .accounts({
+            owner: client.publicKey,
+            blocklist: blocklistPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("AlreadyBlocked");
+      }
+    });
+
+    it("should accept bids again after unblocking", async () => {
+      await program.methods
+        .unblockBidder(freelancer.publicKey)
+        .accounts({
+          owner: client.publicKey,
+          blocklist: blocklistPda,
+        })
+        .signers([client])
+        .rpc();
+      
+      const blocklist = await program.account.blocklist.fetch(blocklistPda);
+      expect(blocklist.blocked).to.have.lengthOf(0);
+      
+      await submitBid();
+    });
+  });
+
+  // ============================================================================
+  // REPUTATION GATE TESTS
+  // ============================================================================
+  
+  describe("Reputation Gates", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let freelancerProfilePda: PublicKey;
+    
+    const postGatedTask = async (
+      minTasksCompleted: number,
+      minAverageRating: number,
+      requiredSkills: string[] = []
+    ) => {
+      const task = Keypair.generate();
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask("Gated Task", "Description", new anchor.BN(1000000), milestones, deadline, {
+          bidBond: new anchor.BN(0),
+          visibility: { public: {} },
+          minTasksCompleted,
+          minAverageRating,
+          requiredSkills,
+          minSkillEndorsements: 0,
+          minStake: new anchor.BN(0),
+        }, { development: {} }, await currentCategoryPage(),
+        null)
+        .accounts({
+          owner: client.publicKey,
+          task: task.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+      return task.publicKey;
+    };
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      
+      [freelancerProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .initializeAgentProfile("New Agent")
+        .accounts({
+          owner: freelancer.publicKey,
+          profile: freelancerProfilePda,
+          referrerProfile: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+    });
+
+    it("should reject a bidder below the completed-task threshold", async () => {
+      const taskPubkey = await postGatedTask(3, 0);
+      const bid = Keypair.generate();
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: freelancerProfilePda,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InsufficientReputation");
+      }
+    });
+
+    it("should reject an unrated bidder when a minimum rating is required", async () => {
+      const taskPubkey = await postGatedTask(0, 4);
+      const bid = Keypair.generate();
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: freelancerProfilePda,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InsufficientReputation");
+      }
+    });
+
+    it("should require the task's skills on the bidder profile", async () => {
+      const tag = "data-labeling";
+      const taskPubkey = await postGatedTask(0, 0, [tag]);
+      
+      try {
+        const bid = Keypair.generate();
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: freelancerProfilePda,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MissingRequiredSkill");
+      }
+      
+      await program.methods
+        .addSkillTag(tag)
+        .accounts({
+          owner: freelancer.publicKey,
+          profile: freelancerProfilePda,
+          skillIndex: getSkillIndexPda(tag),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: freelancerProfilePda,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      expect(bidAccount.bidder.toBase58()).to.equal(freelancer.publicKey.toBase58());
+    });
+
+    it("should reject a gated bid that omits the bidder profile", async () => {
+      const taskPubkey = await postGatedTask(1, 0);
+      const bid = Keypair.generate();
+      
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InsufficientReputation");
+      }
+    });
+
+    it("should let new agents bid on tasks without thresholds", async () => {
+      const taskPubkey = await postGatedTask(0, 0);
+      const bid = Keypair.generate();
+      
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: freelancerProfilePda,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      expect(getBidStatus(bidAccount.status)).to.equal("Pending");
+    });
+  });
+
+  // ============================================================================
+  // BID BOND TESTS
+  // ============================================================================
+  
+  describe("Bid Bonds", () => {
+    const BOND = new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 10);
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    let bidKeypair: Keypair;
+    let bidPubkey: PublicKey;
+    let bidBondPda: PublicKey;
+    
+    const submitBondedBid = async (timeline: anchor.BN) => {
+      await program.methods
+        .submitBid(new anchor.BN(1000000), timeline, "Bonded proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: bidBondPda,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bidKeypair])
+        .rpc();
+    };
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      
+      taskKeypair = Keypair.generate();
+      taskPubkey = taskKeypair.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask("Bonded Task", "Description", new anchor.BN(1000000), milestones, deadline, {
+          bidBond: BOND,
+          visibility: { public: {} },
+          minTasksCompleted: 0,
+          minAverageRating: 0,
+          requiredSkills: [],
+          minSkillEndorsements: 0,
+          minStake: new anchor.BN(0),
+        }, { development: {} }, await currentCategoryPage(),
+        null)
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, taskKeypair])
+        .rpc();
+      
+      bidKeypair = Keypair.generate();
+      bidPubkey = bidKeypair.publicKey;
+      [bidBondPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bid_bond"), bidPubkey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should lock the bond when submitting a bid", async () => {
+      await submitBondedBid(new anchor.BN(86400));
+      
+      const bidAccount = await program.account.bid.fetch(bidPubkey);
+      expect(bidAccount.bond.toNumber()).to.equal(BOND.toNumber());
+      
+      const bond = await program.account.bidBond.fetch(bidBondPda);
+      expect(bond.bidder.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(bond.amount.toNumber()).to.equal(BOND.toNumber());
+      
+      const bondLamports = await provider.connection.getBalance(bidBondPda);
+      expect(bondLamports).to.be.greaterThan(BOND.toNumber());
+    });
+
+    it("should fail to bid without a bond on a bonded task", async () => {
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "No bond", [])
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: null,
+            bidderManifest: null,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([freelancer, bidKeypair])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidBondMismatch");
+      }
+    });
+
+    it("should return the bond when the bid is withdrawn", async () => {
+      await submitBondedBid(new anchor.BN(86400));
+      
+      await program.methods
+        .withdrawBid()
+        .accounts({
+          bidder: freelancer.publicKey,
+          bid: bidPubkey,
+          bidBond: bidBondPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const bondInfo = await provider.connection.getAccountInfo(bidBondPda);
+      expect(bondInfo).to.be.null;
+    });
+
+    it("should return the bond after the bid is rejected", async () => {
+      await submitBondedBid(new anchor.BN(86400));
+      
+      await program.methods
+        .rejectBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+        })
+        .signers([client])
+        .rpc();
+      
+      await program.methods
+        .reclaimBidBond()
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: bidBondPda,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const bondInfo = await provider.connection.getAccountInfo(bidBondPda);
+      expect(bondInfo).to.be.null;
+    });
+
+    it("should let a losing bidder withdraw the bid after reclaiming the bond", async () => {
+      await submitBondedBid(new anchor.BN(86400));
+      
+      const winner = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        winner.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      const winningBid = Keypair.generate();
+      const [winningBond] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bid_bond"), winningBid.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Winning proposal", [])
+        .accounts({
+          bidder: winner.publicKey,
+          task: taskPubkey,
+          bid: winningBid.publicKey,
+          bidBond: winningBond,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([winner, winningBid])
+        .rpc();
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: winningBid.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      await program.methods
+        .reclaimBidBond()
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: bidBondPda,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const bidAccount = await program.account.bid.fetch(bidPubkey);
+      expect(bidAccount.bond.toNumber()).to.equal(0);
+      
+      await program.methods
+        .withdrawBid()
+        .accounts({
+          bidder: freelancer.publicKey,
+          bid: bidPubkey,
+          bidBond: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const bidInfo = await provider.connection.getAccountInfo(bidPubkey);
+      expect(bidInfo).to.be.null;
+    });
+
+    it("should fail to reclaim the bond while the bid is pending", async () => {
+      await submitBondedBid(new anchor.BN(86400));
+      
+      try {
+        await program.methods
+          .reclaimBidBond()
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidBond: bidBondPda,
+          })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidBondLocked");
+      }
+    });
+
+    it("should forfeit the bond to the client once the winner's timeline runs out", async () => {
+      await submitBondedBid(new anchor.BN(1));
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      await sleep(2000);
+      
+      const clientBalanceBefore = await provider.connection.getBalance(client.publicKey);
+      await program.methods
+        .forfeitBidBond()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: bidBondPda,
+        })
+        .signers([client])
+        .rpc();
+      
+      const clientBalanceAfter = await provider.connection.getBalance(client.publicKey);
+      expect(clientBalanceAfter).to.be.greaterThan(clientBalanceBefore);
+    });
+
+    it("should forfeit the bond when the winner delivers after the timeline", async () => {
+      await submitBondedBid(new anchor.BN(1));
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      await sleep(2000);
+      
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      // Completing late can't front-run the client's forfeit
+      try {
+        await program.methods
+          .reclaimBidBond()
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidBond: bidBondPda,
+          })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidBondLocked");
+      }
+      
+      await program.methods
+        .forfeitBidBond()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: bidBondPda,
+        })
+        .signers([client])
+        .rpc();
+      
+      const bondInfo = await provider.connection.getAccountInfo(bidBondPda);
+      expect(bondInfo).to.be.null;
+    });
+
+    it("should fail to forfeit the bond before the winner's timeline runs out", async () => {
+      await submitBondedBid(new anchor.BN(86400));
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      try {
+        await program.methods
+          .forfeitBidBond()
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidBond: bidBondPda,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidBondNotForfeitable");
+      }
+    });
+
+    it("should keep the bond locked after the client refunds an undelivered task", async () => {
+      await createProfile(freelancer, "Freelancer");
+      
+      // Repost with a deadline that passes during the test so the refund is allowed
+      taskKeypair = Keypair.generate();
+      taskPubkey = taskKeypair.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 4);
+      await program.methods
+        .postTask("Short Task", "Description", new anchor.BN(1000000), milestones, deadline, {
+          bidBond: BOND,
+          visibility: { public: {} },
+          minTasksCompleted: 0,
+          minAverageRating: 0,
+          requiredSkills: [],
+          minSkillEndorsements: 0,
+          minStake: new anchor.BN(0),
+        }, { development: {} }, await currentCategoryPage(),
+        null)
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, taskKeypair])
+        .rpc();
+      
+      await submitBondedBid(new anchor.BN(1));
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        client.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        client,
+        tokenMint,
+        clientTokenAccount,
+        client.publicKey,
+        1000000
+      );
+      
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      await sleep(5000);
+      
+      await program.methods
+        .requestRefund()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          clientTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+      
+      try {
+        await program.methods
+          .reclaimBidBond()
+          .accounts({
+            bidder: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidBond: bidBondPda,
+          })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidBondLocked");
+      }
+    });
+  });
+
+  // ============================================================================
+  // DELEGATED KEY TESTS
+  // ============================================================================
+  
+  describe("Delegated Keys", () => {
+    let client: Keypair;
+    let owner: Keypair;
+    let hotKey: Keypair;
+    let taskPubkey: PublicKey;
+    let delegationPda: PublicKey;
+    
+    const delegate = async (permissions: number, maxBidAmount: number) => {
+      await program.methods
+        .createDelegation(
+          hotKey.publicKey,
+          permissions,
+          new anchor.BN(maxBidAmount),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 86400)
+        )
+        .accounts({
+          owner: owner.publicKey,
+          profile: getProfilePda(owner.publicKey),
+          delegation: delegationPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+    };
+    
+    const bidAsHotKey = async (amount: number): Promise<PublicKey> => {
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(amount), new anchor.BN(86400), "Bid from hot key", [])
+        .accounts({
+          bidder: hotKey.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: delegationPda,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([hotKey, bid])
+        .rpc();
+      return bid.publicKey;
+    };
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      owner = Keypair.generate();
+      hotKey = Keypair.generate();
+      
+      for (const kp of [client, owner, hotKey]) {
+        const sig = await provider.connection.requestAirdrop(
+          kp.publicKey,
+          2 * anchor.web3.LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(sig);
+        await sleep(500);
+      }
+      
+      await createProfile(client, "Client");
+      await createProfile(owner, "Agent Operator");
+      
+      [delegationPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("delegation"), getProfilePda(owner.publicKey).toBuffer(), hotKey.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      const task = Keypair.generate();
+      taskPubkey = task.publicKey;
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Task",
+          "Description",
+          new anchor.BN(1000000),
+          [{ description: "All", amount: new anchor.BN(1000000), completed: false, paid: false, approvals: 0 }],
+          deadline,
+          null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+    });
+
+    it("should let a delegate bid on the owner's behalf", async () => {
+      await delegate(PERMISSION_SUBMIT_BID, 2000000);
+      const bidPubkey = await bidAsHotKey(1000000);
+      
+      const bid = await program.account.bid.fetch(bidPubkey);
+      expect(bid.bidder.toBase58()).to.equal(owner.publicKey.toBase58());
+    });
+
+    it("should reject a delegated bid above the cap", async () => {
+      await delegate(PERMISSION_SUBMIT_BID, 500000);
+      
+      try {
+        await bidAsHotKey(1000000);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("DelegationCapExceeded");
+      }
+    });
+
+    it("should reject a delegated bid without the bid permission", async () => {
+      await delegate(PERMISSION_COMPLETE_MILESTONE, 2000000);
+      
+      try {
+        await bidAsHotKey(1000000);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("DelegationNotPermitted");
+      }
+    });
+
+    it("should let a delegate complete a milestone on an accepted bid", async () => {
+      await delegate(PERMISSION_SUBMIT_BID | PERMISSION_COMPLETE_MILESTONE, 2000000);
+      const bidPubkey = await bidAsHotKey(1000000);
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: hotKey.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: delegationPda,
+        })
+        .signers([hotKey])
+        .rpc();
+      
+      const task = await program.account.task.fetch(taskPubkey);
+      expect(task.milestones[0].completed).to.be.true;
+    });
+
+    it("should let a delegate raise a dispute with the evidence permission", async () => {
+      await delegate(PERMISSION_SUBMIT_BID | PERMISSION_SUBMIT_EVIDENCE, 2000000);
+      const bidPubkey = await bidAsHotKey(1000000);
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        client.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        client,
+        tokenMint,
+        clientTokenAccount,
+        client.publicKey,
+        1000000
+      );
+      
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(owner.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      const [disputePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("dispute"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .raiseDispute("Milestone was rejected without review")
+        .accounts({
+          party: hotKey.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: delegationPda,
+          dispute: disputePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([hotKey])
+        .rpc();
+      
+      const dispute = await program.account.dispute.fetch(disputePda);
+      expect(dispute.raisedBy.toBase58()).to.equal(owner.publicKey.toBase58());
+    });
+
+    it("should stop a revoked delegate immediately", async () => {
+      await delegate(PERMISSION_SUBMIT_BID, 2000000);
+      
+      await program.methods
+        .revokeDelegation()
+        .accounts({
+          owner: owner.publicKey,
+          delegation: delegationPda,
+        })
+        .signers([owner])
+        .rpc();
+      
+      try {
+        await bidAsHotKey(1000000);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("AccountNotInitialized");
+      }
+    });
+  });
+
+  // ============================================================================
+  // AGENT MANIFEST TESTS
+  // ============================================================================
+  
+  describe("Agent Manifests", () => {
+    let client: Keypair;
+    let agent: Keypair;
+    let manifestPda: PublicKey;
+    
+    const manifestParams = (available: boolean): ManifestParams => ({
+      categories: [{ development: {} }, { audit: {} }],
+      maxConcurrentTasks: 3,
+      minMilestoneAmount: new anchor.BN(500000),
+      mintPricing: [],
+      available,
+      extendedManifestHash: Array.from(createHash("sha256").update("manifest.json").digest()),
+    });
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      agent = Keypair.generate();
+      
+      for (const kp of [client, agent]) {
+        const sig = await provider.connection.requestAirdrop(
+          kp.publicKey,
+          2 * anchor.web3.LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(sig);
+        await sleep(500);
+      }
+      
+      await createProfile(client, "Client");
+      await createProfile(agent, "Agent");
+      
+      [manifestPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("manifest"), getProfilePda(agent.publicKey).toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .initializeAgentManifest(manifestParams(true))
+        .accounts({
+          owner: agent.publicKey,
+          profile: getProfilePda(agent.publicKey),
+          manifest: manifestPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([agent])
+        .rpc();
+    });
+
+    it("should create and update a manifest", async () => {
+      let manifest = await program.account.agentManifest.fetch(manifestPda);
+      expect(manifest.schemaVersion).to.equal(1);
+      expect(manifest.revision).to.equal(0);
+      expect(manifest.categories).to.have.length(2);
+      expect(manifest.maxConcurrentTasks).to.equal(3);
+      expect(manifest.available).to.be.true;
+      
+      await program.methods
+        .updateAgentManifest(manifestParams(false))
+        .accounts({
+          owner: agent.publicKey,
+          manifest: manifestPda,
+        })
+        .signers([agent])
+        .rpc();
+      
+      manifest = await program.account.agentManifest.fetch(manifestPda);
+      expect(manifest.revision).to.equal(1);
+      expect(manifest.available).to.be.false;
+    });
+
+    it("should reject a bid from an unavailable agent", async () => {
+      await program.methods
+        .updateAgentManifest(manifestParams(false))
+        .accounts({
+          owner: agent.publicKey,
+          manifest: manifestPda,
+        })
+        .signers([agent])
+        .rpc();
+      
+      const task = Keypair.generate();
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Task",
+          "Description",
+          new anchor.BN(1000000),
+          [{ description: "All", amount: new anchor.BN(1000000), completed: false, paid: false, approvals: 0 }],
+          deadline,
+          null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: task.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+      
+      const bid = Keypair.generate();
+      try {
+        await program.methods
+          .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+          .accounts({
+            bidder: agent.publicKey,
+            task: task.publicKey,
+            bid: bid.publicKey,
+            bidBond: null,
+            delegation: null,
+            bidderProfile: getProfilePda(agent.publicKey),
+            bidderManifest: manifestPda,
+            bidderStake: null,
+            ownerBlocklist: getBlocklistPda(client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([agent, bid])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("AgentUnavailable");
+      }
+    });
+
+    it("should fail to update another agent's manifest", async () => {
+      try {
+        await program.methods
+          .updateAgentManifest(manifestParams(false))
+          .accounts({
+            owner: client.publicKey,
+            manifest: manifestPda,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("Unauthorized");
+      }
+    });
+  });
+
+  // ============================================================================
+  // STAKING AND DISPUTE TESTS
+  // ============================================================================
+  
+  describe("Staking and Disputes", () => {
+    const STAKE = new anchor.BN(anchor.web3.LAMPORTS_PER_SOL);
+    
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskPubkey: PublicKey;
+    let freelancerProfilePda: PublicKey;
+    let stakeVaultPda: PublicKey;
+    
+    const depositStake = async (amount: anchor.BN) => {
+      await program.methods
+        .depositStake(amount)
+        .accounts({
+          owner: freelancer.publicKey,
+          profile: freelancerProfilePda,
+          stakeVault: stakeVaultPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+    };
+    
+    const submitBid = async (bidderStake: PublicKey | null): Promise<PublicKey> => {
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Staked proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      return bid.publicKey;
+    };
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      freelancerProfilePda = await createProfile(freelancer, "Freelancer");
+      stakeVaultPda = getStakeVaultPda(freelancerProfilePda);
+      
+      const task = Keypair.generate();
+      taskPubkey = task.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask("Staked Task", "Description", new anchor.BN(1000000), milestones, deadline, {
+          bidBond: new anchor.BN(0),
+          visibility: { public: {} },
+          minTasksCompleted: 0,
+          minAverageRating: 0,
+          requiredSkills: [],
+          minSkillEndorsements: 0,
+          minStake: STAKE,
+        }, { development: {} }, await currentCategoryPage(),
+        null)
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+    });
+
+    it("should deposit stake into the profile vault", async () => {
+      await depositStake(STAKE);
+      
+      const vault = await program.account.stakeVault.fetch(stakeVaultPda);
+      expect(vault.profile.toBase58()).to.equal(freelancerProfilePda.toBase58());
+      expect(vault.owner.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(vault.amount.toString()).to.equal(STAKE.toString());
+      expect(vault.lockedContracts).to.equal(0);
+    });
+
+    it("should reject bids without enough stake", async () => {
+      try {
+        await submitBid(null);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InsufficientStake");
+      }
+      
+      await depositStake(STAKE.divn(2));
+      try {
+        await submitBid(stakeVaultPda);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InsufficientStake");
+      }
+    });
+
+    it("should lock stake while a bid is accepted", async () => {
+      await depositStake(STAKE);
+      const bidPubkey = await submitBid(stakeVaultPda);
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: stakeVaultPda,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const vault = await program.account.stakeVault.fetch(stakeVaultPda);
+      expect(vault.lockedContracts).to.equal(1);
+      const bid = await program.account.bid.fetch(bidPubkey);
+      expect(bid.stakeLocked).to.be.true;
+      
+      try {
+        await program.methods
+          .requestUnstake(STAKE)
+          .accounts({
+            owner: freelancer.publicKey,
+            stakeVault: stakeVaultPda,
+          })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("StakeLocked");
+      }
+    });
+
+    it("should enforce the unstake cooldown", async () => {
+      await depositStake(STAKE);
+      await program.methods
+        .requestUnstake(STAKE)
+        .accounts({
+          owner: freelancer.publicKey,
+          stakeVault: stakeVaultPda,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const vault = await program.account.stakeVault.fetch(stakeVaultPda);
+      expect(vault.pendingUnstake.toString()).to.equal(STAKE.toString());
+      
+      // Pending unstake no longer counts towards bidding requirements
+      try {
+        await submitBid(stakeVaultPda);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InsufficientStake");
+      }
+      
+      try {
+        await program.methods
+          .withdrawStake()
+          .accounts({
+            owner: freelancer.publicKey,
+            stakeVault: stakeVaultPda,
+          })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("UnstakeCooldown");
+      }
+    });
+
+    it("should slash stake to the client when a dispute is lost", async () => {
+      await depositStake(STAKE);
+      const bidPubkey = await submitBid(stakeVaultPda);
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: stakeVaultPda,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        client.publicKey
+      );
+      const freelancerTokenAccount = await createAccount(
+        provider.connection,
+        freelancer,
+        tokenMint,
+        freelancer.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        client,
+        tokenMint,
+        clientTokenAccount,
+        client.publicKey,
+        1000000
+      );
+      
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: freelancerProfilePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      const [disputePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("dispute"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .raiseDispute("Work was never delivered")
+        .accounts({
+          party: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: null,
+          dispute: disputePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      let taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(getTaskStatus(taskAccount.status)).to.equal("Disputed");
+      
+      // Payouts are frozen until the arbiter rules
+      try {
+        await program.methods
+          .approveRelease(0)
+          .accounts({
+            approver: client.publicKey,
+            task: taskPubkey,
+            escrow: escrowPda,
+            escrowTokenAccount: escrowTokenPda,
+            freelancerTokenAccount,
+            clientProfile: getProfilePda(client.publicKey),
+            freelancerProfile: freelancerProfilePda,
+            config: configPda,
+            treasuryTokenAccount: null,
+            referrerProfile: null,
+            referrerTokenAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
+      }
+      
+      const clientLamportsBefore = await provider.connection.getBalance(client.publicKey);
+      
+      // Slash 25% of the stake
+      await program.methods
+        .resolveDispute({ client: {} }, 2500)
+        .accounts({
+          arbiter: provider.wallet.publicKey,
+          config: configPda,
+          task: taskPubkey,
+          bid: bidPubkey,
+          dispute: disputePda,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          clientTokenAccount,
+          freelancerTokenAccount,
+          client: client.publicKey,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: freelancerProfilePda,
+          freelancerStake: stakeVaultPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      
+      const slashed = STAKE.divn(4);
+      const dispute = await program.account.dispute.fetch(disputePda);
+      expect(dispute.ruling).to.deep.equal({ client: {} });
+      expect(dispute.slashed.toString()).to.equal(slashed.toString());
+      
+      const vault = await program.account.stakeVault.fetch(stakeVaultPda);
+      expect(vault.amount.toString()).to.equal(STAKE.sub(slashed).toString());
+      expect(vault.lockedContracts).to.equal(0);
+      
+      const clientLamportsAfter = await provider.connection.getBalance(client.publicKey);
+      expect(clientLamportsAfter - clientLamportsBefore).to.equal(slashed.toNumber());
+      
+      const refund = await getAccount(provider.connection, clientTokenAccount);
+      expect(Number(refund.amount)).to.equal(1000000);
+      
+      taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(getTaskStatus(taskAccount.status)).to.equal("Cancelled");
+      
+      const freelancerProfile = await program.account.agentProfile.fetch(freelancerProfilePda);
+      expect(freelancerProfile.disputesLost).to.equal(1);
+      expect(freelancerProfile.activeContracts).to.equal(0);
+    });
+  });
+
+  // ============================================================================
+  // TASK UPDATE TESTS
+  // ============================================================================
+  
+  describe("Task Update", () => {
+    let client: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    let milestones: Milestone[];
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      
+      taskKeypair = Keypair.generate();
+      taskPubkey = taskKeypair.publicKey;
+      milestones = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Original Title", "Original Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, taskKeypair])
+        .rpc();
+    });
+
+    it("should update task description", async () => {
+      const newDescription = "Updated Description";
+      
+      await program.methods
+        .updateTask(newDescription, null, null, null, [])
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.description).to.equal(newDescription);
+    });
+
+    it("should resize the task account to fit its description", async () => {
+      const updateDescription = (description: string) =>
+        program.methods
+          .updateTask(description, null, null, null, [])
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+      
+      const before = await provider.connection.getAccountInfo(taskPubkey);
+      const oldDescription = (await program.account.task.fetch(taskPubkey)).description;
+      
+      // Growing charges the owner the extra rent
+      const longDescription = "x".repeat(1000);
+      const ownerBefore = await provider.connection.getBalance(client.publicKey);
+      await updateDescription(longDescription);
+      
+      const grown = await provider.connection.getAccountInfo(taskPubkey);
+      expect(grown!.data.length).to.equal(
+        before!.data.length - oldDescription.length + longDescription.length
+      );
+      const ownerAfterGrow = await provider.connection.getBalance(client.publicKey);
+      expect(ownerBefore - ownerAfterGrow).to.equal(grown!.lamports - before!.lamports);
+      
+      // Shrinking refunds it
+      await updateDescription("Short");
+      const shrunk = await provider.connection.getAccountInfo(taskPubkey);
+      expect(shrunk!.data.length).to.equal(before!.data.length - oldDescription.length + 5);
+      const ownerAfterShrink = await provider.connection.getBalance(client.publicKey);
+      expect(ownerAfterShrink - ownerAfterGrow).to.equal(grown!.lamports - shrunk!.lamports);
+    });
+
+    it("should update task budget", async () => {
+      const newBudget = new anchor.BN(2000000);
+      
+      await program.methods
+        .updateTask(null, newBudget, null, null, [
+          { edit: { index: 0, description: null, amount: newBudget } },
+        ])
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.budget.toNumber()).to.equal(newBudget.toNumber());
+      expect(taskAccount.milestones[0].amount.toNumber()).to.equal(newBudget.toNumber());
+      expect(taskAccount.scopeVersion).to.equal(1);
+    });
+
+    it("should fail to change the budget without matching milestones", async () => {
+      try {
+        await program.methods
+          .updateTask(null, new anchor.BN(2000000), null, null, [])
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MilestoneAmountMismatch");
+      }
+    });
+
+    it("should add, edit and remove milestones", async () => {
+      await program.methods
+        .updateTask(null, new anchor.BN(1500000), null, null, [
+          { add: { description: "Milestone 2", amount: new anchor.BN(500000) } },
+          { add: { description: "Milestone 3", amount: new anchor.BN(250000) } },
+          { edit: { index: 0, description: "Kickoff", amount: new anchor.BN(750000) } },
+          { edit: { index: 1, description: null, amount: new anchor.BN(750000) } },
+          { remove: { index: 2 } },
+        ])
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.milestones.map((m) => m.description)).to.deep.equal([
+        "Kickoff",
+        "Milestone 2",
+      ]);
+      expect(taskAccount.milestones.map((m) => m.amount.toNumber())).to.deep.equal([
+        750000,
+        750000,
+      ]);
+    });
+
+    it("should not accept bids made before the scope changed", async () => {
+      const freelancer = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      await program.methods
+        .updateTask(null, null, null, null, [
+          { edit: { index: 0, description: "Bigger scope", amount: null } },
+        ])
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(bidAccount.scopeVersion).to.be.lessThan(taskAccount.scopeVersion);
+      
+      try {
+        await program.methods
+          .acceptBid()
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+            bid: bid.publicKey,
+            bidderStake: null,
+            ...(await categoryAccountsFor(taskPubkey)),
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("StaleBid");
+      }
+    });
+
+    it("should update task deadline", async () => {
+      const newDeadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 14);
+      
+      await program.methods
+        .updateTask(null, null, newDeadline, null, [])
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.deadline.toNumber()).to.equal(newDeadline.toNumber());
+    });
+
+    it("should fail to update task when not owner", async () => {
+      const attacker = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        attacker.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      try {
+        await program.methods
+          .updateTask("Hacked", null, null, null, [])
+          .accounts({
+            owner: attacker.publicKey,
+            task: taskPubkey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([attacker])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
+      }
+    });
+
+    it("should fail to update non-open task", async () => {
+      // First accept a bid to make task InProgress
+      const freelancer = Keypair.generate();
+      let sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(900000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      // Try to update
+      try {
+        await program.methods
+          .updateTask("Updated", null, null, null, [])
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
+      }
+    });
+  });
+
+  // ============================================================================
+  // TASK OWNERSHIP TRANSFER TESTS
+  // ============================================================================
+  
+  describe("Task Ownership Transfer", () => {
+    let client: Keypair;
+    let newOwner: Keypair;
+    let freelancer: Keypair;
+    let taskPubkey: PublicKey;
+    
+    const propose = (to: PublicKey) =>
+      program.methods
+        .transferTaskOwnership(to)
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+        })
+        .signers([client])
+        .rpc();
+    
+    const accept = (signer: Keypair, escrow: PublicKey | null = null) =>
+      program.methods
+        .acceptTaskOwnership()
+        .accounts({
+          newOwner: signer.publicKey,
+          task: taskPubkey,
+          oldOwnerProfile: getProfilePda(client.publicKey),
+          newOwnerProfile: getProfilePda(signer.publicKey),
+          escrow,
+        })
+        .signers([signer])
+        .rpc();
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      newOwner = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      for (const kp of [client, newOwner, freelancer]) {
+        const sig = await provider.connection.requestAirdrop(
+          kp.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(sig);
+        await sleep(500);
+      }
+      
+      await createProfile(client, "Team Wallet A");
+      await createProfile(newOwner, "Team Wallet B");
+      await createProfile(freelancer, "Freelancer");
+      
+      const task = Keypair.generate();
+      taskPubkey = task.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Team Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+    });
+
+    it("should move an open task once the new owner accepts", async () => {
+      await propose(newOwner.publicKey);
+      
+      let taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.owner.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(taskAccount.pendingOwner.toBase58()).to.equal(newOwner.publicKey.toBase58());
+      
+      await accept(newOwner);
+      
+      taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.owner.toBase58()).to.equal(newOwner.publicKey.toBase58());
+      expect(taskAccount.pendingOwner).to.be.null;
+      
+      const oldProfile = await program.account.agentProfile.fetch(getProfilePda(client.publicKey));
+      const newProfile = await program.account.agentProfile.fetch(getProfilePda(newOwner.publicKey));
+      expect(oldProfile.openTasks).to.equal(0);
+      expect(newProfile.openTasks).to.equal(1);
+    });
+
+    it("should only let the proposed owner accept", async () => {
+      await propose(newOwner.publicKey);
+      
+      try {
+        await accept(freelancer);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NotPendingOwner");
+      }
+      
+      // Proposing the current owner withdraws the proposal
+      await propose(client.publicKey);
+      try {
+        await accept(newOwner);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NotPendingOwner");
+      }
+    });
+
+    it("should hand escrow client rights to the new owner", async () => {
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        client.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        client,
+        tokenMint,
+        clientTokenAccount,
+        client.publicKey,
+        1000000
+      );
+      
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bid.publicKey,
+          escrow: escrowPda,
+          clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      await propose(newOwner.publicKey);
+      
+      try {
+        await accept(newOwner);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MissingEscrowAccount");
+      }
+      
+      await accept(newOwner, escrowPda);
+      
+      const escrow = await program.account.escrow.fetch(escrowPda);
+      expect(escrow.client.toBase58()).to.equal(newOwner.publicKey.toBase58());
+      
+      const oldProfile = await program.account.agentProfile.fetch(getProfilePda(client.publicKey));
+      const newProfile = await program.account.agentProfile.fetch(getProfilePda(newOwner.publicKey));
+      expect(oldProfile.activeEscrows).to.equal(0);
+      expect(newProfile.activeEscrows).to.equal(1);
+      expect(newProfile.openTasks).to.equal(1);
+    });
+  });
+
+  // ============================================================================
+  // RELEASE APPROVAL TESTS
+  // ============================================================================
+  
+  describe("Release Approvals", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let approvers: Keypair[];
+    let taskPubkey: PublicKey;
+    let escrowPda: PublicKey;
+    let escrowTokenPda: PublicKey;
+    let freelancerTokenAccount: PublicKey;
+    
+    const approve = (signer: Keypair) =>
+      program.methods
+        .approveRelease(0)
+        .accounts({
+          approver: signer.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          freelancerTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          config: configPda,
+          treasuryTokenAccount: null,
+          referrerProfile: null,
+          referrerTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+    
+    const revoke = (signer: Keypair) =>
+      program.methods
+        .revokeApproval(0)
+        .accounts({
+          approver: signer.publicKey,
+          task: taskPubkey,
+        })
+        .signers([signer])
+        .rpc();
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      approvers = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      
+      for (const kp of [client, freelancer, ...approvers]) {
+        const sig = await provider.connection.requestAirdrop(
+          kp.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(sig);
+        await sleep(500);
+      }
+      
+      await createProfile(client, "Finance Team");
+      await createProfile(freelancer, "Freelancer");
+      
+      const task = Keypair.generate();
+      taskPubkey = task.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Audited Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+      
+      await program.methods
+        .setReleaseApprovers(approvers.map((kp) => kp.publicKey), 2)
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+        })
+        .signers([client])
+        .rpc();
+      
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        client.publicKey
+      );
+      freelancerTokenAccount = await createAccount(
+        provider.connection,
+        freelancer,
+        tokenMint,
+        freelancer.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        client,
+        tokenMint,
+        clientTokenAccount,
+        client.publicKey,
+        1000000
+      );
+      
+      [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bid.publicKey,
+          escrow: escrowPda,
+          clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+    });
+
+    it("should release only once the threshold is reached", async () => {
+      await approve(approvers[0]);
+      
+      let taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.milestones[0].paid).to.be.false;
+      expect(taskAccount.milestones[0].approvals).to.equal(0b001);
+      
+      try {
+        await approve(approvers[0]);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("AlreadyApproved");
+      }
+      
+      await approve(approvers[2]);
+      
+      taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.milestones[0].paid).to.be.true;
+      
+      const balance = await getAccount(provider.connection, freelancerTokenAccount);
+      expect(Number(balance.amount)).to.equal(1000000);
+    });
+
+    it("should let an approver revoke before execution", async () => {
+      await approve(approvers[0]);
+      await revoke(approvers[0]);
+      
+      let taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.milestones[0].approvals).to.equal(0);
+      
+      try {
+        await revoke(approvers[0]);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("ApprovalNotFound");
+      }
+      
+      await approve(approvers[1]);
+      
+      taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.milestones[0].paid).to.be.false;
+    });
+
+    it("should keep the approver set when the task changes owner", async () => {
+      const newOwner = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        newOwner.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      await createProfile(newOwner, "New Finance Team");
+      
+      await approve(approvers[0]);
+      
+      await program.methods
+        .transferTaskOwnership(newOwner.publicKey)
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+        })
+        .signers([client])
+        .rpc();
+      await program.methods
+        .acceptTaskOwnership()
+        .accounts({
+          newOwner: newOwner.publicKey,
+          task: taskPubkey,
+          oldOwnerProfile: getProfilePda(client.publicKey),
+          newOwnerProfile: getProfilePda(newOwner.publicKey),
+          escrow: escrowPda,
+        })
+        .signers([newOwner])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.approvers).to.have.length(3);
+      expect(taskAccount.approvalThreshold).to.equal(2);
+      expect(taskAccount.milestones[0].approvals).to.equal(0b001);
+      
+      // The new owner can neither replace the set nor approve outside it
+      try {
+        await program.methods
+          .setReleaseApprovers([newOwner.publicKey], 1)
+          .accounts({
+            owner: newOwner.publicKey,
+            task: taskPubkey,
+          })
+          .signers([newOwner])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("ApproversLocked");
+      }
+      
+      try {
+        await program.methods
+          .approveRelease(0)
+          .accounts({
+            approver: newOwner.publicKey,
+            task: taskPubkey,
+            escrow: escrowPda,
+            escrowTokenAccount: escrowTokenPda,
+            freelancerTokenAccount,
+            clientProfile: getProfilePda(newOwner.publicKey),
+            freelancerProfile: getProfilePda(freelancer.publicKey),
+            config: configPda,
+            treasuryTokenAccount: null,
+            referrerProfile: null,
+            referrerTokenAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([newOwner])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NotApprover");
+      }
+      
+      await program.methods
+        .approveRelease(0)
+        .accounts({
+          approver: approvers[1].publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          freelancerTokenAccount,
+          clientProfile: getProfilePda(newOwner.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          config: configPda,
+          treasuryTokenAccount: null,
+          referrerProfile: null,
+          referrerTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([approvers[1]])
+        .rpc();
+      
+      const balance = await getAccount(provider.connection, freelancerTokenAccount);
+      expect(Number(balance.amount)).to.equal(1000000);
+    });
+
+    it("should not let the owner clear approvers once escrow is funded", async () => {
+      try {
+        await program.methods
+          .setReleaseApprovers([], 0)
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("ApproversLocked");
+      }
+      
+      // The owner still cannot release alone
+      try {
+        await approve(client);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("NotApprover");
+      }
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.approvers).to.have.length(3);
+      expect(taskAccount.milestones[0].paid).to.be.false;
+    });
+
+    it("should reject approvals from outside the approver set", async () => {
+      // The owner is no longer an approver once a set is designated
+      for (const signer of [client, freelancer]) {
+        try {
+          await approve(signer);
+          expect.fail("Should have thrown an error");
+        } catch (error: any) {
+          expect(error.toString()).to.include("NotApprover");
+        }
+      }
+    });
+  });
+
+  // ============================================================================
+  // TASK CANCELLATION TESTS
+  // ============================================================================
+  
+  describe("Task Cancellation", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    let milestones: Milestone[];
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      
+      taskKeypair = Keypair.generate();
+      taskPubkey = taskKeypair.publicKey;
+      milestones = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, taskKeypair])
+        .rpc();
+    });
+
+    it("should cancel an open task without escrow", async () => {
+      await program.methods
+        .cancelTask()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(getTaskStatus(taskAccount.status)).to.equal("Cancelled");
+    });
+
+    it("should fail to cancel task when not owner", async () => {
+      try {
+        await program.methods
+          .cancelTask()
+          .accounts({
+            owner: freelancer.publicKey,
+            task: taskPubkey,
+            ...(await categoryAccountsFor(taskPubkey)),
+          })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
+      }
+    });
+
+    it("should fail to cancel task with escrow", async () => {
+      // Accept bid and fund escrow first
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(900000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      // Create token mint and fund escrow (simplified - in real test would need token setup)
+      // For this test, we verify the constraint works by checking escrow_account
+      // The escrow test below covers the full flow
+    });
+  });
+
+  // ============================================================================
+  // TASK EXPIRATION TESTS
+  // ============================================================================
+  
+  describe("Task Expiration", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    let bidKeypair: Keypair;
+    let bidPubkey: PublicKey;
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      await createProfile(client, "Client");
+      
+      taskKeypair = Keypair.generate();
+      taskPubkey = taskKeypair.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      // Short deadline so the task can be expired within the test
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 4);
+      await program.methods
+        .postTask(
+          "Short Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, taskKeypair])
+        .rpc();
+      
+      bidKeypair = Keypair.generate();
+      bidPubkey = bidKeypair.publicKey;
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(1), "Quick job", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bidKeypair])
+        .rpc();
+    });
+
+    it("should set bid expiry to the task deadline", async () => {
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      const bidAccount = await program.account.bid.fetch(bidPubkey);
+      expect(bidAccount.expiresAt.toNumber()).to.equal(taskAccount.deadline.toNumber());
+    });
+
+    it("should fail to expire a task before its deadline", async () => {
+      try {
+        await program.methods
+          .expireTask()
+          .accounts({
+            task: taskPubkey,
+            ownerProfile: getProfilePda(client.publicKey),
+            ...(await categoryAccountsFor(taskPubkey)),
+          })
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("TaskNotExpired");
+      }
+    });
+
+    it("should let anyone expire a past-deadline task and close its bids", async () => {
+      await sleep(6000);
+      
+      await program.methods
+        .expireTask()
+        .accounts({
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(getTaskStatus(taskAccount.status)).to.equal("Expired");
+      
+      const bidderBalanceBefore = await provider.connection.getBalance(freelancer.publicKey);
+      await program.methods
+        .closeExpiredBid()
+        .accounts({
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidder: freelancer.publicKey,
+          bidBond: null,
+        })
+        .rpc();
+      
+      const bidInfo = await provider.connection.getAccountInfo(bidPubkey);
+      expect(bidInfo).to.be.null;
+      const bidderBalanceAfter = await provider.connection.getBalance(freelancer.publicKey);
+      expect(bidderBalanceAfter).to.be.greaterThan(bidderBalanceBefore);
+    });
+
+    it("should fail to accept an expired bid", async () => {
+      await sleep(6000);
+      
+      try {
+        await program.methods
+          .acceptBid()
+          .accounts({
+            owner: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidderStake: null,
+            ...(await categoryAccountsFor(taskPubkey)),
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidExpired");
+      }
+    });
+
+    it("should fail to close a bid that has not expired", async () => {
+      try {
+        await program.methods
+          .closeExpiredBid()
+          .accounts({
+            task: taskPubkey,
+            bid: bidPubkey,
+            bidder: freelancer.publicKey,
+            bidBond: null,
+          })
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BidNotExpired");
+      }
+    });
+  });
+
+  // ============================================================================
+  // ESCROW AND TOKEN TESTS
+  // ============================================================================
+  
+  describe("Escrow and Payment Flow", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    let bidKeypair: Keypair;
+    let bidPubkey: PublicKey;
+    let tokenMint: PublicKey;
+    let clientTokenAccount: PublicKey;
+    let freelancerTokenAccount: PublicKey;
+    let milestones: Milestone[];
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      // Create token mint
+      tokenMint = await createMint(
+        provider.connection,
+        client,
+        client.publicKey,
+        null,
+        6
+      );
+      
+      // Create token accounts
+      clientTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        client.publicKey
+      );
+      
+      freelancerTokenAccount = await createAccount(
+        provider.connection,
+        freelancer,
+        tokenMint,
+        freelancer.publicKey
+      );
+      
+      // Mint tokens to client
+      await mintTo(
+        provider.connection,
+        client,
+        tokenMint,
+        clientTokenAccount,
+        client.publicKey,
+        10000000
+      );
+      
+      await createProfile(client, "Client");
+      await createProfile(freelancer, "Freelancer");
+      
+      taskKeypair = Keypair.generate();
+      taskPubkey = taskKeypair.publicKey;
+      milestones = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+        {
+          description: "Milestone 2",
+          amount: new anchor.BN(2000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Task", "Description", new anchor.BN(3000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, taskKeypair])
+        .rpc();
+      
+      bidKeypair = Keypair.generate();
+      bidPubkey = bidKeypair.publicKey;
+      await program.methods
+        .submitBid(new anchor.BN(3000000), new anchor.BN(86400 * 5), "My proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bidKeypair])
+        .rpc();
+      
+      // Accept bid
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+    });
+
+    it("should fund escrow with tokens", async () => {
+      // Derive escrow PDA
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      
+      // Derive escrow token account PDA
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount: clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Verify escrow was created
+      const escrowAccount = await program.account.escrow.fetch(escrowPda);
+      expect(escrowAccount.task.toBase58()).to.equal(taskPubkey.toBase58());
+      expect(escrowAccount.client.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(escrowAccount.freelancer.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(escrowAccount.totalAmount.toNumber()).to.equal(3000000);
+      expect(escrowAccount.releasedAmount.toNumber()).to.equal(0);
+      expect(escrowAccount.tokenMint.toBase58()).to.equal(tokenMint.toBase58());
+      
+      // Verify tokens were transferred
+      const escrowTokenBalance = await getAccount(provider.connection, escrowTokenPda);
+      expect(Number(escrowTokenBalance.amount)).to.equal(3000000);
+      
+      const clientBalance = await getAccount(provider.connection, clientTokenAccount);
+      expect(Number(clientBalance.amount)).to.equal(7000000); // Started with 10M, transferred 3M
+    });
+
+    it("should complete a milestone", async () => {
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.milestones[0].completed).to.be.true;
+      expect(taskAccount.milestones[1].completed).to.be.false;
+    });
+
+    it("should release payment for completed milestone", async () => {
+      // First fund escrow
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount: clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Complete milestone
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      // Release payment
+      await program.methods
+        .approveRelease(0)
+        .accounts({
+          approver: client.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          config: configPda,
+          treasuryTokenAccount: null,
+          referrerProfile: null,
+          referrerTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Verify payment was released
+      const escrowAccount = await program.account.escrow.fetch(escrowPda);
+      expect(escrowAccount.releasedAmount.toNumber()).to.equal(1000000);
+      
+      const freelancerBalance = await getAccount(provider.connection, freelancerTokenAccount);
+      expect(Number(freelancerBalance.amount)).to.equal(1000000);
+      
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(taskAccount.milestones[0].paid).to.be.true;
+    });
+
+    it("should fail to release payment for uncompleted milestone", async () => {
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount: clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Try to release without completing milestone
+      try {
+        await program.methods
+          .approveRelease(0)
+          .accounts({
+            approver: client.publicKey,
+            task: taskPubkey,
+            escrow: escrowPda,
+            escrowTokenAccount: escrowTokenPda,
+            freelancerTokenAccount: freelancerTokenAccount,
+            clientProfile: getProfilePda(client.publicKey),
+            freelancerProfile: getProfilePda(freelancer.publicKey),
+            config: configPda,
+            treasuryTokenAccount: null,
+            referrerProfile: null,
+            referrerTokenAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MilestoneNotCompleted");
+      }
+    });
+
+    it("should fail to release payment twice for same milestone", async () => {
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount: clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Complete and release
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      await program.methods
+        .approveRelease(0)
+        .accounts({
+          approver: client.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          config: configPda,
+          treasuryTokenAccount: null,
+          referrerProfile: null,
+          referrerTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Try to release again
+      try {
+        await program.methods
+          .approveRelease(0)
+          .accounts({
+            approver: client.publicKey,
+            task: taskPubkey,
+            escrow: escrowPda,
+            escrowTokenAccount: escrowTokenPda,
+            freelancerTokenAccount: freelancerTokenAccount,
+            clientProfile: getProfilePda(client.publicKey),
+            freelancerProfile: getProfilePda(freelancer.publicKey),
+            config: configPda,
+            treasuryTokenAccount: null,
+            referrerProfile: null,
+            referrerTokenAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MilestoneAlreadyPaid");
+      }
+    });
+
+    it("should request refund for cancelled task", async () => {
+      // Fund escrow
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount: clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Update task status to cancelled (in a real scenario this would happen via dispute resolution)
+      // For this test, we verify the escrow logic is correct
+      
+      // Get initial client balance
+      const initialClientBalance = await getAccount(provider.connection, clientTokenAccount);
+      
+      // Request refund
+      await program.methods
+        .requestRefund()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          clientTokenAccount: clientTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Verify refund was received
+      const finalClientBalance = await getAccount(provider.connection, clientTokenAccount);
+      expect(Number(finalClientBalance.amount) - Number(initialClientBalance.amount)).to.equal(3000000);
+    });
+
+    it("should complete full payment flow and mark task as completed", async () => {
+      // Fund escrow
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount: clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Complete and release both milestones
+      for (let i = 0; i < 2; i++) {
+        await program.methods
+          .completeMilestone(i)
+          .accounts({
+            freelancer: freelancer.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            delegation: null,
+          })
+          .signers([freelancer])
+          .rpc();
+        
+        await program.methods
+          .approveRelease(i)
+          .accounts({
+            approver: client.publicKey,
+            task: taskPubkey,
+            escrow: escrowPda,
+            escrowTokenAccount: escrowTokenPda,
+            freelancerTokenAccount: freelancerTokenAccount,
+            clientProfile: getProfilePda(client.publicKey),
+            freelancerProfile: getProfilePda(freelancer.publicKey),
+            config: configPda,
+            treasuryTokenAccount: null,
+            referrerProfile: null,
+            referrerTokenAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client])
+          .rpc();
+      }
+      
+      // Verify task is completed
+      const taskAccount = await program.account.task.fetch(taskPubkey);
+      expect(getTaskStatus(taskAccount.status)).to.equal("Completed");
+      
+      // Verify freelancer received full payment
+      const freelancerBalance = await getAccount(provider.connection, freelancerTokenAccount);
+      expect(Number(freelancerBalance.amount)).to.equal(3000000);
+      
+      // Verify both profiles recorded the contract
+      const clientProfile = await program.account.agentProfile.fetch(getProfilePda(client.publicKey));
+      expect(clientProfile.tasksPosted).to.equal(1);
+      expect(clientProfile.totalSpent.toNumber()).to.equal(3000000);
+      
+      const freelancerProfile = await program.account.agentProfile.fetch(getProfilePda(freelancer.publicKey));
+      expect(freelancerProfile.tasksCompleted).to.equal(1);
+      expect(freelancerProfile.totalEarned.toNumber()).to.equal(3000000);
+    });
+  });
+
+  // ============================================================================
+  // REFERRAL TESTS
+  // ============================================================================
+  
+  describe("Referral Rewards", () => {
+    let referrer: Keypair;
+    let client: Keypair;
+    let freelancer: Keypair;
+    let referrerProfilePda: PublicKey;
+    let freelancerProfilePda: PublicKey;
+    let taskPubkey: PublicKey;
+    let escrowPda: PublicKey;
+    let escrowTokenPda: PublicKey;
+    let freelancerTokenAccount: PublicKey;
+    let referrerTokenAccount: PublicKey;
+    let treasuryTokenAccount: PublicKey;
+    
+    const setFees = (protocolFeeBps: number, referralShareBps: number) =>
+      program.methods
+        .setFees(provider.wallet.publicKey, protocolFeeBps, referralShareBps)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+        })
+        .rpc();
+    
+    const release = (
+      referrerProfile: PublicKey | null,
+      referrerToken: PublicKey | null
+    ) =>
+      program.methods
+        .approveRelease(0)
+        .accounts({
+          approver: client.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          freelancerTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: freelancerProfilePda,
+          config: configPda,
+          treasuryTokenAccount,
+          referrerProfile,
+          referrerTokenAccount: referrerToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+    
+    // Fees are marketplace-wide, so leave them off for the other suites
+    afterEach(async () => {
+      await setFees(0, 0);
+    });
+
+    // Takes a referred freelancer's first task up to its final payment
+    beforeEach(async () => {
+      referrer = Keypair.generate();
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      for (const kp of [referrer, client, freelancer]) {
+        const sig = await provider.connection.requestAirdrop(
+          kp.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(sig);
+        await sleep(500);
+      }
+      
+      referrerProfilePda = await createProfile(referrer, "Referrer");
+      await createProfile(client, "Client");
+      freelancerProfilePda = await createProfile(freelancer, "Freelancer", referrerProfilePda);
+      
+      const task = Keypair.generate();
+      taskPubkey = task.publicKey;
+      const milestones: Milestone[] = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Referred Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, task])
+        .rpc();
+      
+      const bid = Keypair.generate();
+      await program.methods
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
+        .accounts({
+          bidder: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer, bid])
+        .rpc();
+      
+      await program.methods
+        .acceptBid()
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
+        })
+        .signers([client])
+        .rpc();
+      
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        client.publicKey
+      );
+      freelancerTokenAccount = await createAccount(
+        provider.connection,
+        freelancer,
+        tokenMint,
+        freelancer.publicKey
+      );
+      referrerTokenAccount = await createAccount(
+        provider.connection,
+        referrer,
+        tokenMint,
+        referrer.publicKey
+      );
+      treasuryTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        provider.wallet.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        client,
+        tokenMint,
+        clientTokenAccount,
+        client.publicKey,
+        1000000
+      );
+      
+      [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bid.publicKey,
+          escrow: escrowPda,
+          clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: freelancerProfilePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bid.publicKey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+    });
+
+    it("should pay the referrer a share of the protocol fee on the first completed task", async () => {
+      let freelancerProfile = await program.account.agentProfile.fetch(freelancerProfilePda);
+      expect(freelancerProfile.referrer.toBase58()).to.equal(referrerProfilePda.toBase58());
+      expect(freelancerProfile.referralPaid).to.be.false;
+      
+      // 10% protocol fee, half of which goes to the referrer
+      await setFees(1000, 5000);
+      
+      // The client cannot skip the referrer on the completing payment
+      try {
+        await release(null, null);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("MissingReferrerAccount");
+      }
+      
+      await release(referrerProfilePda, referrerTokenAccount);
+      
+      const freelancerBalance = await getAccount(provider.connection, freelancerTokenAccount);
+      expect(Number(freelancerBalance.amount)).to.equal(900000);
+      const referrerBalance = await getAccount(provider.connection, referrerTokenAccount);
+      expect(Number(referrerBalance.amount)).to.equal(50000);
+      const treasuryBalance = await getAccount(provider.connection, treasuryTokenAccount);
+      expect(Number(treasuryBalance.amount)).to.equal(50000);
+      
+      freelancerProfile = await program.account.agentProfile.fetch(freelancerProfilePda);
+      expect(freelancerProfile.referralPaid).to.be.true;
+      expect(freelancerProfile.totalEarned.toNumber()).to.equal(900000);
+    });
+
+    it("should settle the referral without a reward when there is no fee", async () => {
+      await release(null, null);
+      
+      const freelancerBalance = await getAccount(provider.connection, freelancerTokenAccount);
+      expect(Number(freelancerBalance.amount)).to.equal(1000000);
+      
+      const freelancerProfile = await program.account.agentProfile.fetch(freelancerProfilePda);
+      expect(freelancerProfile.referralPaid).to.be.true;
+    });
+
+    it("should skip the reward when the referrer has closed their profile", async () => {
+      await program.methods
+        .closeAgentProfile()
+        .accounts({
+          owner: referrer.publicKey,
+          profile: referrerProfilePda,
+        })
+        .signers([referrer])
+        .rpc();
+      
+      await setFees(1000, 5000);
+      await release(referrerProfilePda, null);
+      
+      const freelancerBalance = await getAccount(provider.connection, freelancerTokenAccount);
+      expect(Number(freelancerBalance.amount)).to.equal(900000);
+      const treasuryBalance = await getAccount(provider.connection, treasuryTokenAccount);
+      expect(Number(treasuryBalance.amount)).to.equal(100000);
+      
+      const freelancerProfile = await program.account.agentProfile.fetch(freelancerProfilePda);
+      expect(freelancerProfile.referralPaid).to.be.true;
+    });
+  });
+
+  // ============================================================================
+  // REVIEW SUBMISSION TESTS
+  // ============================================================================
+  
+  describe("Review Submission", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let taskKeypair: Keypair;
+    let taskPubkey: PublicKey;
+    let bidKeypair: Keypair;
+    let bidPubkey: PublicKey;
+    let milestones: Milestone[];
+    
+    // Fund escrow, complete and pay the single milestone so the task is Completed
+    const completeTask = async () => {
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(provider.connection, client, tokenMint, client.publicKey);
+      const freelancerTokenAccount = await createAccount(provider.connection, freelancer, tokenMint, freelancer.publicKey);
+      await mintTo(provider.connection, client, tokenMint, clientTokenAccount, client.publicKey, 10000000);
+      
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        program.programId
+      );
+      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .fundEscrow()
+        .accounts({
+          client: client.publicKey,
+          task: taskPubkey,
+          acceptedBid: bidPubkey,
+          escrow: escrowPda,
+          clientTokenAccount: clientTokenAccount,
+          escrowTokenAccount: escrowTokenPda,
+          tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([client])
+        .rpc();
+      
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      await program.methods
+        .approveRelease(0)
+        .accounts({
+          approver: client.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          config: configPda,
+          treasuryTokenAccount: null,
+          referrerProfile: null,
+          referrerTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+    };
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
+      
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      
+      // Initialize profiles for both parties
+      const [clientProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), client.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .initializeAgentProfile("Client")
+        .accounts({
+          owner: client.publicKey,
+          profile: clientProfilePda,
+          referrerProfile: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      await program.methods
+        .initializeAgentProfile("Freelancer")
+        .accounts({
+          owner: freelancer.publicKey,
+          profile: freelancerProfilePda,
+          referrerProfile: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      taskKeypair = Keypair.generate();
+      taskPubkey = taskKeypair.publicKey;
+      milestones = [
+        {
+          description: "Milestone 1",
+          amount: new anchor.BN(1000000),
+          completed: false,
+          paid: false,
+          approvals: 0,
+        },
+      ];
+      
+      const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
+      await program.methods
+        .postTask(
+          "Task", "Description", new anchor.BN(1000000), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
+        .accounts({
+          owner: client.publicKey,
+          task: taskPubkey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, taskKeypair])
         .rpc();
       
-      // Complete milestone
+      bidKeypair = Keypair.generate();
+      bidPubkey = bidKeypair.publicKey;
       await program.methods
-        .completeMilestone(0)
+        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal", [])
         .accounts({
-          freelancer: freelancer.publicKey,
+          bidder: freelancer.publicKey,
           task: taskPubkey,
           bid: bidPubkey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
+          systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer])
+        .signers([freelancer, bidKeypair])
         .rpc();
       
-      // Release payment
       await program.methods
-        .releasePayment(0)
+        .acceptBid()
         .accounts({
-          client: client.publicKey,
+          owner: client.publicKey,
           task: taskPubkey,
-          escrow: escrowPda,
-          escrowTokenAccount: escrowTokenPda,
-          freelancerTokenAccount: freelancerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          bid: bidPubkey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskPubkey)),
         })
         .signers([client])
         .rpc();
-      
-      // Verify payment was released
-      const escrowAccount = await program.account.escrow.fetch(escrowPda);
-      expect(escrowAccount.releasedAmount.toNumber()).to.equal(1000000);
-      
-      const freelancerBalance = await getAccount(provider.connection, freelancerTokenAccount);
-      expect(Number(freelancerBalance.amount)).to.equal(1000000);
-      
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(taskAccount.milestones[0].paid).to.be.true;
     });
 
-    it("should fail to release payment for uncompleted milestone", async () => {
+    it("should submit review by client for completed task", async () => {
+      // Create a mock completed task by updating status directly via completing milestones
+      // First, fund escrow with tokens
+      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const clientTokenAccount = await createAccount(provider.connection, client, tokenMint, client.publicKey);
+      const freelancerTokenAccount = await createAccount(provider.connection, freelancer, tokenMint, freelancer.publicKey);
+      
+      await mintTo(provider.connection, client, tokenMint, clientTokenAccount, client.publicKey, 10000000);
+      
       const [escrowPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("escrow"), taskPubkey.toBuffer()],
         program.programId
@@ -1627,6 +6743,8 @@ describe("Agora - Agent Task Marketplace", () => {
           clientTokenAccount: clientTokenAccount,
           escrowTokenAccount: escrowTokenPda,
           tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: SYSVAR_RENT_PUBKEY,
@@ -1634,339 +6752,644 @@ describe("Agora - Agent Task Marketplace", () => {
         .signers([client])
         .rpc();
       
-      // Try to release without completing milestone
-      try {
-        await program.methods
-          .releasePayment(0)
+      // Complete milestone and release payment to mark task completed
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          freelancer: freelancer.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          delegation: null,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      await program.methods
+        .approveRelease(0)
+        .accounts({
+          approver: client.publicKey,
+          task: taskPubkey,
+          escrow: escrowPda,
+          escrowTokenAccount: escrowTokenPda,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          config: configPda,
+          treasuryTokenAccount: null,
+          referrerProfile: null,
+          referrerTokenAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Submit review
+      const reviewPda = getReviewPda(taskPubkey, client.publicKey);
+      const rating = 5;
+      const reviewText = "Excellent work! Highly recommended.";
+      
+      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      await program.methods
+        .submitReview(rating, reviewText)
+        .accounts({
+          reviewer: client.publicKey,
+          task: taskPubkey,
+          bid: bidPubkey,
+          reviewee: freelancer.publicKey,
+          revieweeProfile: freelancerProfilePda,
+          review: reviewPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      
+      // Verify review was created
+      const reviewAccount = await program.account.review.fetch(reviewPda);
+      expect(reviewAccount.reviewer.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(reviewAccount.reviewee.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(reviewAccount.task.toBase58()).to.equal(taskPubkey.toBase58());
+      expect(reviewAccount.rating).to.equal(rating);
+      expect(reviewAccount.weight.toNumber()).to.equal(1000000);
+      expect(reviewAccount.reviewText).to.equal(reviewText);
+      
+      // Verify profile was updated with rating
+      const freelancerProfile = await program.account.agentProfile.fetch(freelancerProfilePda);
+      expect(freelancerProfile.ratingSum).to.equal(rating);
+      expect(freelancerProfile.ratingCount).to.equal(1);
+      
+      const reputation: ReputationView = await program.methods
+        .getReputation()
+        .accounts({
+          profile: freelancerProfilePda,
+        })
+        .view();
+      expect(reputation.score.toNumber()).to.be.closeTo(rating * REPUTATION_SCALE, 10);
+      expect(reputation.ratingCount).to.equal(1);
+    });
+
+    it("should fail to submit review for non-completed task", async () => {
+      const reviewPda = getReviewPda(taskPubkey, client.publicKey);
+      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      try {
+        await program.methods
+          .submitReview(5, "Great work")
+          .accounts({
+            reviewer: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            reviewee: freelancer.publicKey,
+            revieweeProfile: freelancerProfilePda,
+            review: reviewPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
+      }
+    });
+
+    it("should fail to submit review with invalid rating (0)", async () => {
+      const reviewPda = getReviewPda(taskPubkey, client.publicKey);
+      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      try {
+        await program.methods
+          .submitReview(0, "Bad rating")
+          .accounts({
+            reviewer: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            reviewee: freelancer.publicKey,
+            revieweeProfile: freelancerProfilePda,
+            review: reviewPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidRating");
+      }
+    });
+
+    it("should fail to submit review with invalid rating (6)", async () => {
+      const reviewPda = getReviewPda(taskPubkey, client.publicKey);
+      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      try {
+        await program.methods
+          .submitReview(6, "Bad rating")
+          .accounts({
+            reviewer: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            reviewee: freelancer.publicKey,
+            revieweeProfile: freelancerProfilePda,
+            review: reviewPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidRating");
+      }
+    });
+
+    it("should fail to submit review with text exceeding max length", async () => {
+      const reviewPda = getReviewPda(taskPubkey, client.publicKey);
+      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      const longReview = "a".repeat(MAX_REVIEW_LEN + 1);
+      
+      try {
+        await program.methods
+          .submitReview(5, longReview)
+          .accounts({
+            reviewer: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            reviewee: freelancer.publicKey,
+            revieweeProfile: freelancerProfilePda,
+            review: reviewPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("ReviewTooLong");
+      }
+    });
+
+    it("should fail when user tries to review themselves", async () => {
+      await completeTask();
+      
+      try {
+        await program.methods
+          .submitReview(5, "I'm great")
+          .accounts({
+            reviewer: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            reviewee: client.publicKey,
+            revieweeProfile: getProfilePda(client.publicKey),
+            review: getReviewPda(taskPubkey, client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidReviewee");
+      }
+    });
+
+    it("should fail to review a wallet that isn't the counterparty", async () => {
+      await completeTask();
+      const stranger = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        stranger.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
+      await createProfile(stranger, "Stranger");
+      
+      try {
+        await program.methods
+          .submitReview(1, "Never worked with them")
+          .accounts({
+            reviewer: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            reviewee: stranger.publicKey,
+            revieweeProfile: getProfilePda(stranger.publicKey),
+            review: getReviewPda(taskPubkey, client.publicKey),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidReviewee");
+      }
+    });
+
+    it("should fail to review the same task twice", async () => {
+      await completeTask();
+      
+      const submit = () =>
+        program.methods
+          .submitReview(5, "Great work")
           .accounts({
-            client: client.publicKey,
+            reviewer: client.publicKey,
             task: taskPubkey,
-            escrow: escrowPda,
-            escrowTokenAccount: escrowTokenPda,
-            freelancerTokenAccount: freelancerTokenAccount,
-            tokenProgram: TOKEN_PROGRAM_ID,
+            bid: bidPubkey,
+            reviewee: freelancer.publicKey,
+            revieweeProfile: getProfilePda(freelancer.publicKey),
+            review: getReviewPda(taskPubkey, client.publicKey),
+            systemProgram: SystemProgram.programId,
           })
           .signers([client])
           .rpc();
+      
+      await submit();
+      try {
+        await submit();
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("MilestoneNotCompleted");
+        expect(error.toString()).to.include("already in use");
       }
+      
+      const freelancerProfile = await program.account.agentProfile.fetch(getProfilePda(freelancer.publicKey));
+      expect(freelancerProfile.ratingCount).to.equal(1);
     });
 
-    it("should fail to release payment twice for same milestone", async () => {
-      const [escrowPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), taskPubkey.toBuffer()],
-        program.programId
-      );
-      
-      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
-        program.programId
-      );
+    it("should let the reviewee respond to a review once", async () => {
+      await completeTask();
+      const reviewPda = getReviewPda(taskPubkey, client.publicKey);
       
       await program.methods
-        .fundEscrow()
+        .submitReview(2, "Late delivery")
         .accounts({
-          client: client.publicKey,
+          reviewer: client.publicKey,
           task: taskPubkey,
-          acceptedBid: bidPubkey,
-          escrow: escrowPda,
-          clientTokenAccount: clientTokenAccount,
-          escrowTokenAccount: escrowTokenPda,
-          tokenMint: tokenMint,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          bid: bidPubkey,
+          reviewee: freelancer.publicKey,
+          revieweeProfile: getProfilePda(freelancer.publicKey),
+          review: reviewPda,
           systemProgram: SystemProgram.programId,
-          rent: SYSVAR_RENT_PUBKEY,
         })
         .signers([client])
         .rpc();
       
-      // Complete and release
-      await program.methods
-        .completeMilestone(0)
-        .accounts({
-          freelancer: freelancer.publicKey,
-          task: taskPubkey,
-          bid: bidPubkey,
-        })
-        .signers([freelancer])
-        .rpc();
+      const respond = (signer: Keypair, response: string) =>
+        program.methods
+          .respondToReview(response)
+          .accounts({
+            reviewee: signer.publicKey,
+            review: reviewPda,
+          })
+          .signers([signer])
+          .rpc();
+      
+      try {
+        await respond(client, "I am not the reviewee");
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("Unauthorized");
+      }
+      
+      await respond(freelancer, "The scope changed twice mid-milestone");
+      
+      const review = await program.account.review.fetch(reviewPda);
+      expect(review.response).to.equal("The scope changed twice mid-milestone");
+      expect(review.respondedAt.toNumber()).to.be.greaterThan(0);
+      
+      try {
+        await respond(freelancer, "Second reply");
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("AlreadyResponded");
+      }
+    });
+
+    it("should remove a flagged review from the profile aggregate", async () => {
+      await completeTask();
+      const reviewPda = getReviewPda(taskPubkey, client.publicKey);
+      const freelancerProfilePda = getProfilePda(freelancer.publicKey);
       
       await program.methods
-        .releasePayment(0)
+        .submitReview(1, "Abusive review")
         .accounts({
-          client: client.publicKey,
+          reviewer: client.publicKey,
           task: taskPubkey,
-          escrow: escrowPda,
-          escrowTokenAccount: escrowTokenPda,
-          freelancerTokenAccount: freelancerTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          bid: bidPubkey,
+          reviewee: freelancer.publicKey,
+          revieweeProfile: freelancerProfilePda,
+          review: reviewPda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([client])
         .rpc();
       
-      // Try to release again
-      try {
-        await program.methods
-          .releasePayment(0)
+      const flag = (moderator: PublicKey, signers: Keypair[]) =>
+        program.methods
+          .flagReview()
           .accounts({
-            client: client.publicKey,
-            task: taskPubkey,
-            escrow: escrowPda,
-            escrowTokenAccount: escrowTokenPda,
-            freelancerTokenAccount: freelancerTokenAccount,
-            tokenProgram: TOKEN_PROGRAM_ID,
+            moderator,
+            config: configPda,
+            review: reviewPda,
+            revieweeProfile: freelancerProfilePda,
           })
-          .signers([client])
+          .signers(signers)
           .rpc();
+      
+      try {
+        await flag(freelancer.publicKey, [freelancer]);
         expect.fail("Should have thrown an error");
       } catch (error: any) {
-        expect(error.toString()).to.include("MilestoneAlreadyPaid");
+        expect(error.toString()).to.include("Unauthorized");
       }
-    });
-
-    it("should request refund for cancelled task", async () => {
-      // Fund escrow
-      const [escrowPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), taskPubkey.toBuffer()],
-        program.programId
-      );
       
-      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
-        program.programId
-      );
+      // The provider wallet is the config admin
+      await flag(provider.wallet.publicKey, []);
+      
+      const review = await program.account.review.fetch(reviewPda);
+      expect(review.flaggedBy.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+      expect(review.rating).to.equal(1);
+      
+      const profile = await program.account.agentProfile.fetch(freelancerProfilePda);
+      expect(profile.ratingSum).to.equal(0);
+      expect(profile.ratingCount).to.equal(0);
       
+      try {
+        await flag(provider.wallet.publicKey, []);
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("ReviewAlreadyFlagged");
+      }
+    });
+
+    it("should endorse a declared skill once per completed task", async () => {
+      const skill = "smart-contracts";
       await program.methods
-        .fundEscrow()
+        .addSkillTag(skill)
         .accounts({
-          client: client.publicKey,
-          task: taskPubkey,
-          acceptedBid: bidPubkey,
-          escrow: escrowPda,
-          clientTokenAccount: clientTokenAccount,
-          escrowTokenAccount: escrowTokenPda,
-          tokenMint: tokenMint,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          owner: freelancer.publicKey,
+          profile: getProfilePda(freelancer.publicKey),
+          skillIndex: getSkillIndexPda(skill),
           systemProgram: SystemProgram.programId,
-          rent: SYSVAR_RENT_PUBKEY,
         })
-        .signers([client])
+        .signers([freelancer])
         .rpc();
       
-      // Update task status to cancelled (in a real scenario this would happen via dispute resolution)
-      // For this test, we verify the escrow logic is correct
+      await completeTask();
       
-      // Get initial client balance
-      const initialClientBalance = await getAccount(provider.connection, clientTokenAccount);
+      const endorse = () =>
+        program.methods
+          .endorseSkill(skill)
+          .accounts({
+            client: client.publicKey,
+            task: taskPubkey,
+            bid: bidPubkey,
+            freelancerProfile: getProfilePda(freelancer.publicKey),
+            endorsement: getEndorsementPda(taskPubkey, skill),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
       
-      // Request refund
-      await program.methods
-        .requestRefund()
-        .accounts({
-          client: client.publicKey,
-          task: taskPubkey,
-          escrow: escrowPda,
-          escrowTokenAccount: escrowTokenPda,
-          clientTokenAccount: clientTokenAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([client])
-        .rpc();
+      await endorse();
       
-      // Verify refund was received
-      const finalClientBalance = await getAccount(provider.connection, clientTokenAccount);
-      expect(Number(finalClientBalance.amount) - Number(initialClientBalance.amount)).to.equal(3000000);
-    });
-
-    it("should complete full payment flow and mark task as completed", async () => {
-      // Fund escrow
-      const [escrowPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), taskPubkey.toBuffer()],
-        program.programId
+      const endorsement: Endorsement = await program.account.endorsement.fetch(
+        getEndorsementPda(taskPubkey, skill)
       );
+      expect(endorsement.endorser.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(endorsement.endorsee.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(endorsement.skill).to.equal(skill);
       
-      const [escrowTokenPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow_token"), escrowPda.toBuffer()],
-        program.programId
-      );
+      const freelancerProfile = await program.account.agentProfile.fetch(getProfilePda(freelancer.publicKey));
+      expect(freelancerProfile.skillEndorsements).to.deep.equal([1]);
+      expect(freelancerProfile.endorsementCount).to.equal(1);
       
+      try {
+        await endorse();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("already in use");
+      }
+    });
+
+    it("should fail to endorse before the task is completed", async () => {
+      const skill = "smart-contracts";
       await program.methods
-        .fundEscrow()
+        .addSkillTag(skill)
         .accounts({
-          client: client.publicKey,
-          task: taskPubkey,
-          acceptedBid: bidPubkey,
-          escrow: escrowPda,
-          clientTokenAccount: clientTokenAccount,
-          escrowTokenAccount: escrowTokenPda,
-          tokenMint: tokenMint,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          owner: freelancer.publicKey,
+          profile: getProfilePda(freelancer.publicKey),
+          skillIndex: getSkillIndexPda(skill),
           systemProgram: SystemProgram.programId,
-          rent: SYSVAR_RENT_PUBKEY,
         })
-        .signers([client])
+        .signers([freelancer])
         .rpc();
       
-      // Complete and release both milestones
-      for (let i = 0; i < 2; i++) {
-        await program.methods
-          .completeMilestone(i)
-          .accounts({
-            freelancer: freelancer.publicKey,
-            task: taskPubkey,
-            bid: bidPubkey,
-          })
-          .signers([freelancer])
-          .rpc();
-        
+      try {
         await program.methods
-          .releasePayment(i)
+          .endorseSkill(skill)
           .accounts({
             client: client.publicKey,
             task: taskPubkey,
-            escrow: escrowPda,
-            escrowTokenAccount: escrowTokenPda,
-            freelancerTokenAccount: freelancerTokenAccount,
-            tokenProgram: TOKEN_PROGRAM_ID,
+            bid: bidPubkey,
+            freelancerProfile: getProfilePda(freelancer.publicKey),
+            endorsement: getEndorsementPda(taskPubkey, skill),
+            systemProgram: SystemProgram.programId,
           })
           .signers([client])
           .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("constraint was violated");
       }
-      
-      // Verify task is completed
-      const taskAccount = await program.account.task.fetch(taskPubkey);
-      expect(getTaskStatus(taskAccount.status)).to.equal("Completed");
-      
-      // Verify freelancer received full payment
-      const freelancerBalance = await getAccount(provider.connection, freelancerTokenAccount);
-      expect(Number(freelancerBalance.amount)).to.equal(3000000);
     });
   });
 
   // ============================================================================
-  // REVIEW SUBMISSION TESTS
+  // BADGE TESTS
   // ============================================================================
   
-  describe("Review Submission", () => {
-    let client: Keypair;
-    let freelancer: Keypair;
-    let taskKeypair: Keypair;
-    let taskPubkey: PublicKey;
-    let bidKeypair: Keypair;
-    let bidPubkey: PublicKey;
-    let milestones: Milestone[];
+  describe("Badges", () => {
+    let agent: Keypair;
+    let agentProfilePda: PublicKey;
     
-    beforeEach(async () => {
-      client = Keypair.generate();
-      freelancer = Keypair.generate();
-      
-      let sig = await provider.connection.requestAirdrop(
-        client.publicKey,
-        10 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(sig);
-      await sleep(500);
-      
-      sig = await provider.connection.requestAirdrop(
-        freelancer.publicKey,
-        10 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(sig);
-      await sleep(500);
-      
-      // Initialize profiles for both parties
-      const [clientProfilePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("profile"), client.publicKey.toBuffer()],
-        program.programId
-      );
-      
-      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
-        program.programId
+    beforeEach(async () => {
+      agent = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        agent.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
       );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
       
+      agentProfilePda = await createProfile(agent, "Agent");
+    });
+
+    it("should let anyone claim a badge for a qualifying profile", async () => {
+      const definition = await createBadgeDefinition("Clean Record", {
+        minTasksCompleted: 0,
+        minTotalEarned: new anchor.BN(0),
+        minAverageRating: 0,
+        maxDisputesLost: 0,
+      });
+      
+      // Claimed and paid for by the provider wallet, not the agent
       await program.methods
-        .initializeAgentProfile("Client")
+        .claimBadge()
         .accounts({
-          owner: client.publicKey,
-          profile: clientProfilePda,
+          payer: provider.wallet.publicKey,
+          definition,
+          profile: agentProfilePda,
+          badge: getBadgePda(definition, agentProfilePda),
           systemProgram: SystemProgram.programId,
         })
-        .signers([client])
         .rpc();
       
-      await program.methods
-        .initializeAgentProfile("Freelancer")
-        .accounts({
-          owner: freelancer.publicKey,
-          profile: freelancerProfilePda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([freelancer])
-        .rpc();
+      const badge = await program.account.badge.fetch(getBadgePda(definition, agentProfilePda));
+      expect(badge.definition.toBase58()).to.equal(definition.toBase58());
+      expect(badge.owner.toBase58()).to.equal(agent.publicKey.toBase58());
       
-      taskKeypair = Keypair.generate();
-      taskPubkey = taskKeypair.publicKey;
-      milestones = [
+      const badgeDefinition = await program.account.badgeDefinition.fetch(definition);
+      expect(badgeDefinition.issued).to.equal(1);
+    });
+
+    it("should fail to claim a badge whose criteria are not met", async () => {
+      const definition = await createBadgeDefinition("First Task", {
+        minTasksCompleted: 1,
+        minTotalEarned: new anchor.BN(0),
+        minAverageRating: 0,
+        maxDisputesLost: null,
+      });
+      
+      try {
+        await program.methods
+          .claimBadge()
+          .accounts({
+            payer: agent.publicKey,
+            definition,
+            profile: agentProfilePda,
+            badge: getBadgePda(definition, agentProfilePda),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([agent])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("BadgeCriteriaNotMet");
+      }
+    });
+
+    it("should fail to create a badge definition as a non-admin", async () => {
+      const id = Math.floor(Math.random() * 0xffffffff);
+      
+      try {
+        await program.methods
+          .createBadgeDefinition(id, "Fake", "", {
+            minTasksCompleted: 0,
+            minTotalEarned: new anchor.BN(0),
+            minAverageRating: 0,
+            maxDisputesLost: null,
+          })
+          .accounts({
+            admin: agent.publicKey,
+            config: configPda,
+            definition: getBadgeDefinitionPda(id),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([agent])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("Unauthorized");
+      }
+    });
+  });
+
+  // ============================================================================
+  // REPUTATION SCORE TESTS
+  // ============================================================================
+  
+  describe("Reputation Score", () => {
+    let client: Keypair;
+    let freelancer: Keypair;
+    let tokenMint: PublicKey;
+    let clientTokenAccount: PublicKey;
+    let freelancerTokenAccount: PublicKey;
+    
+    // Run a single-milestone contract to completion and have the client rate it
+    const rateContract = async (budget: number, rating: number) => {
+      const task = Keypair.generate();
+      const bid = Keypair.generate();
+      const milestones: Milestone[] = [
         {
-          description: "Milestone 1",
-          amount: new anchor.BN(1000000),
+          description: "Deliverable",
+          amount: new anchor.BN(budget),
           completed: false,
           paid: false,
+          approvals: 0,
         },
       ];
       
       const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 86400 * 7);
       await program.methods
-        .postTask("Task", "Description", new anchor.BN(1000000), milestones, deadline)
+        .postTask(
+          "Contract", "Description", new anchor.BN(budget), milestones, deadline, null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
+        )
         .accounts({
           owner: client.publicKey,
-          task: taskPubkey,
+          task: task.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
-        .signers([client, taskKeypair])
+        .signers([client, task])
         .rpc();
       
-      bidKeypair = Keypair.generate();
-      bidPubkey = bidKeypair.publicKey;
       await program.methods
-        .submitBid(new anchor.BN(1000000), new anchor.BN(86400), "Proposal")
+        .submitBid(new anchor.BN(budget), new anchor.BN(86400), "Proposal", [])
         .accounts({
           bidder: freelancer.publicKey,
-          task: taskPubkey,
-          bid: bidPubkey,
+          task: task.publicKey,
+          bid: bid.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
           systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer, bidKeypair])
+        .signers([freelancer, bid])
         .rpc();
       
       await program.methods
         .acceptBid()
         .accounts({
           owner: client.publicKey,
-          task: taskPubkey,
-          bid: bidPubkey,
+          task: task.publicKey,
+          bid: bid.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(task.publicKey)),
         })
         .signers([client])
         .rpc();
-    });
-
-    it("should submit review by client for completed task", async () => {
-      // Create a mock completed task by updating status directly via completing milestones
-      // First, fund escrow with tokens
-      const tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
-      const clientTokenAccount = await createAccount(provider.connection, client, tokenMint, client.publicKey);
-      const freelancerTokenAccount = await createAccount(provider.connection, freelancer, tokenMint, freelancer.publicKey);
-      
-      await mintTo(provider.connection, client, tokenMint, clientTokenAccount, client.publicKey, 10000000);
       
       const [escrowPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), taskPubkey.toBuffer()],
+        [Buffer.from("escrow"), task.publicKey.toBuffer()],
         program.programId
       );
-      
       const [escrowTokenPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("escrow_token"), escrowPda.toBuffer()],
         program.programId
@@ -1976,12 +7399,14 @@ describe("Agora - Agent Task Marketplace", () => {
         .fundEscrow()
         .accounts({
           client: client.publicKey,
-          task: taskPubkey,
-          acceptedBid: bidPubkey,
+          task: task.publicKey,
+          acceptedBid: bid.publicKey,
           escrow: escrowPda,
           clientTokenAccount: clientTokenAccount,
           escrowTokenAccount: escrowTokenPda,
           tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: SYSVAR_RENT_PUBKEY,
@@ -1989,181 +7414,133 @@ describe("Agora - Agent Task Marketplace", () => {
         .signers([client])
         .rpc();
       
-      // Complete milestone and release payment to mark task completed
       await program.methods
         .completeMilestone(0)
         .accounts({
           freelancer: freelancer.publicKey,
-          task: taskPubkey,
-          bid: bidPubkey,
+          task: task.publicKey,
+          bid: bid.publicKey,
+          delegation: null,
         })
         .signers([freelancer])
         .rpc();
       
       await program.methods
-        .releasePayment(0)
+        .approveRelease(0)
         .accounts({
-          client: client.publicKey,
-          task: taskPubkey,
+          approver: client.publicKey,
+          task: task.publicKey,
           escrow: escrowPda,
           escrowTokenAccount: escrowTokenPda,
           freelancerTokenAccount: freelancerTokenAccount,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
+          config: configPda,
+          treasuryTokenAccount: null,
+          referrerProfile: null,
+          referrerTokenAccount: null,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([client])
         .rpc();
       
-      // Submit review
-      const review = Keypair.generate();
-      const rating = 5;
-      const reviewText = "Excellent work! Highly recommended.";
-      
-      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
-        program.programId
-      );
-      
       await program.methods
-        .submitReview(rating, reviewText)
+        .submitReview(rating, "Rated")
         .accounts({
           reviewer: client.publicKey,
-          task: taskPubkey,
-          bid: bidPubkey,
+          task: task.publicKey,
+          bid: bid.publicKey,
           reviewee: freelancer.publicKey,
-          revieweeProfile: freelancerProfilePda,
-          review: review.publicKey,
+          revieweeProfile: getProfilePda(freelancer.publicKey),
+          review: getReviewPda(task.publicKey, client.publicKey),
           systemProgram: SystemProgram.programId,
         })
-        .signers([client, review])
+        .signers([client])
         .rpc();
+    };
+    
+    beforeEach(async () => {
+      client = Keypair.generate();
+      freelancer = Keypair.generate();
       
-      // Verify review was created
-      const reviewAccount = await program.account.review.fetch(review.publicKey);
-      expect(reviewAccount.reviewer.toBase58()).to.equal(client.publicKey.toBase58());
-      expect(reviewAccount.reviewee.toBase58()).to.equal(freelancer.publicKey.toBase58());
-      expect(reviewAccount.task.toBase58()).to.equal(taskPubkey.toBase58());
-      expect(reviewAccount.rating).to.equal(rating);
-      expect(reviewAccount.reviewText).to.equal(reviewText);
-      
-      // Verify profile was updated with rating
-      const freelancerProfile = await program.account.agentProfile.fetch(freelancerProfilePda);
-      expect(freelancerProfile.ratingSum).to.equal(rating);
-      expect(freelancerProfile.ratingCount).to.equal(1);
-    });
-
-    it("should fail to submit review for non-completed task", async () => {
-      const review = Keypair.generate();
-      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
-        program.programId
+      let sig = await provider.connection.requestAirdrop(
+        client.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
       );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
       
-      try {
-        await program.methods
-          .submitReview(5, "Great work")
-          .accounts({
-            reviewer: client.publicKey,
-            task: taskPubkey,
-            bid: bidPubkey,
-            reviewee: freelancer.publicKey,
-            revieweeProfile: freelancerProfilePda,
-            review: review.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([client, review])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("constraint was violated");
-      }
-    });
-
-    it("should fail to submit review with invalid rating (0)", async () => {
-      const review = Keypair.generate();
-      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
-        program.programId
+      sig = await provider.connection.requestAirdrop(
+        freelancer.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
       );
+      await provider.connection.confirmTransaction(sig);
+      await sleep(500);
       
-      try {
-        await program.methods
-          .submitReview(0, "Bad rating")
-          .accounts({
-            reviewer: client.publicKey,
-            task: taskPubkey,
-            bid: bidPubkey,
-            reviewee: freelancer.publicKey,
-            revieweeProfile: freelancerProfilePda,
-            review: review.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([client, review])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("InvalidRating");
-      }
+      await createProfile(client, "Client");
+      await createProfile(freelancer, "Freelancer");
+      
+      tokenMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      clientTokenAccount = await createAccount(provider.connection, client, tokenMint, client.publicKey);
+      freelancerTokenAccount = await createAccount(provider.connection, freelancer, tokenMint, freelancer.publicKey);
+      await mintTo(provider.connection, client, tokenMint, clientTokenAccount, client.publicKey, 100000000);
     });
 
-    it("should fail to submit review with invalid rating (6)", async () => {
-      const review = Keypair.generate();
-      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
-        program.programId
-      );
+    it("should weight ratings by the escrowed amount", async () => {
+      await rateContract(1000000, 5);
+      await rateContract(9000000, 1);
       
-      try {
-        await program.methods
-          .submitReview(6, "Bad rating")
-          .accounts({
-            reviewer: client.publicKey,
-            task: taskPubkey,
-            bid: bidPubkey,
-            reviewee: freelancer.publicKey,
-            revieweeProfile: freelancerProfilePda,
-            review: review.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([client, review])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("InvalidRating");
-      }
+      const freelancerProfile = await program.account.agentProfile.fetch(getProfilePda(freelancer.publicKey));
+      // The plain average treats both reviews equally
+      expect(freelancerProfile.ratingSum / freelancerProfile.ratingCount).to.equal(3);
+      
+      const reputation: ReputationView = await program.methods
+        .getReputation()
+        .accounts({
+          profile: getProfilePda(freelancer.publicKey),
+        })
+        .view();
+      // (5 * 1M + 1 * 9M) / 10M = 1.4
+      expect(reputation.score.toNumber()).to.be.closeTo(1.4 * REPUTATION_SCALE, 1000);
+      expect(reputation.ratingCount).to.equal(2);
     });
 
-    it("should fail to submit review with text exceeding max length", async () => {
-      const review = Keypair.generate();
-      const [freelancerProfilePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("profile"), freelancer.publicKey.toBuffer()],
-        program.programId
-      );
+    it("should award a first-task badge once a contract is completed", async () => {
+      const definition = await createBadgeDefinition("First Task", {
+        minTasksCompleted: 1,
+        minTotalEarned: new anchor.BN(0),
+        minAverageRating: 0,
+        maxDisputesLost: null,
+      });
+      const freelancerProfilePda = getProfilePda(freelancer.publicKey);
       
-      const longReview = "a".repeat(MAX_REVIEW_LEN + 1);
+      await rateContract(1000000, 5);
       
-      try {
-        await program.methods
-          .submitReview(5, longReview)
-          .accounts({
-            reviewer: client.publicKey,
-            task: taskPubkey,
-            bid: bidPubkey,
-            reviewee: freelancer.publicKey,
-            revieweeProfile: freelancerProfilePda,
-            review: review.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([client, review])
-          .rpc();
-        expect.fail("Should have thrown an error");
-      } catch (error: any) {
-        expect(error.toString()).to.include("ReviewTooLong");
-      }
+      await program.methods
+        .claimBadge()
+        .accounts({
+          payer: freelancer.publicKey,
+          definition,
+          profile: freelancerProfilePda,
+          badge: getBadgePda(definition, freelancerProfilePda),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+      
+      const badge = await program.account.badge.fetch(getBadgePda(definition, freelancerProfilePda));
+      expect(badge.profile.toBase58()).to.equal(freelancerProfilePda.toBase58());
     });
 
-    it("should fail when user tries to review themselves", async () => {
-      // This is covered by the constraint that reviewer != reviewee
-      // But we'd need a completed task to test it properly
+    it("should report zero for an unrated profile", async () => {
+      const reputation: ReputationView = await program.methods
+        .getReputation()
+        .accounts({
+          profile: getProfilePda(freelancer.publicKey),
+        })
+        .view();
+      expect(reputation.score.toNumber()).to.equal(0);
+      expect(reputation.weightedAmount.toNumber()).to.equal(0);
     });
   });
 
@@ -2205,6 +7582,7 @@ describe("Agora - Agent Task Marketplace", () => {
         .accounts({
           owner: client.publicKey,
           profile: clientProfilePda,
+          referrerProfile: null,
           systemProgram: SystemProgram.programId,
         })
         .signers([client])
@@ -2215,6 +7593,7 @@ describe("Agora - Agent Task Marketplace", () => {
         .accounts({
           owner: freelancer.publicKey,
           profile: freelancerProfilePda,
+          referrerProfile: null,
           systemProgram: SystemProgram.programId,
         })
         .signers([freelancer])
@@ -2228,18 +7607,21 @@ describe("Agora - Agent Task Marketplace", () => {
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
+          approvals: 0,
         },
         {
           description: "Implementation",
           amount: new anchor.BN(3000000),
           completed: false,
           paid: false,
+          approvals: 0,
         },
         {
           description: "Testing and deployment",
           amount: new anchor.BN(1000000),
           completed: false,
           paid: false,
+          approvals: 0,
         },
       ];
       
@@ -2251,11 +7633,17 @@ describe("Agora - Agent Task Marketplace", () => {
           "Create a comprehensive DeFi dashboard with real-time data visualization",
           new anchor.BN(5000000),
           milestones,
-          deadline
+          deadline,
+          null,
+          { development: {} },
+          await currentCategoryPage(),
+          null
         )
         .accounts({
           owner: client.publicKey,
           task: taskKeypair.publicKey,
+          ownerProfile: getProfilePda(client.publicKey),
+          ...(await categoryPostAccounts()),
           systemProgram: SystemProgram.programId,
         })
         .signers([client, taskKeypair])
@@ -2272,12 +7660,19 @@ describe("Agora - Agent Task Marketplace", () => {
         .submitBid(
           new anchor.BN(4500000),
           new anchor.BN(86400 * 10),
-          "I have 5 years of experience building DeFi applications. I can deliver this in 10 days with high quality."
+          "I have 5 years of experience building DeFi applications. I can deliver this in 10 days with high quality.",
+          []
         )
         .accounts({
           bidder: freelancer.publicKey,
           task: taskKeypair.publicKey,
           bid: bidKeypair.publicKey,
+          bidBond: null,
+          delegation: null,
+          bidderProfile: null,
+          bidderManifest: null,
+          bidderStake: null,
+          ownerBlocklist: getBlocklistPda(client.publicKey),
           systemProgram: SystemProgram.programId,
         })
         .signers([freelancer, bidKeypair])
@@ -2294,6 +7689,8 @@ describe("Agora - Agent Task Marketplace", () => {
           owner: client.publicKey,
           task: taskKeypair.publicKey,
           bid: bidKeypair.publicKey,
+          bidderStake: null,
+          ...(await categoryAccountsFor(taskKeypair.publicKey)),
         })
         .signers([client])
         .rpc();
@@ -2331,6 +7728,8 @@ describe("Agora - Agent Task Marketplace", () => {
           clientTokenAccount: clientTokenAccount,
           escrowTokenAccount: escrowTokenPda,
           tokenMint: tokenMint,
+          clientProfile: getProfilePda(client.publicKey),
+          freelancerProfile: getProfilePda(freelancer.publicKey),
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: SYSVAR_RENT_PUBKEY,
@@ -2349,18 +7748,25 @@ describe("Agora - Agent Task Marketplace", () => {
             freelancer: freelancer.publicKey,
             task: taskKeypair.publicKey,
             bid: bidKeypair.publicKey,
+            delegation: null,
           })
           .signers([freelancer])
           .rpc();
         
         await program.methods
-          .releasePayment(i)
+          .approveRelease(i)
           .accounts({
-            client: client.publicKey,
+            approver: client.publicKey,
             task: taskKeypair.publicKey,
             escrow: escrowPda,
             escrowTokenAccount: escrowTokenPda,
             freelancerTokenAccount: freelancerTokenAccount,
+            clientProfile: getProfilePda(client.publicKey),
+            freelancerProfile: getProfilePda(freelancer.publicKey),
+            config: configPda,
+            treasuryTokenAccount: null,
+            referrerProfile: null,
+            referrerTokenAccount: null,
             tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([client])
@@ -2374,8 +7780,8 @@ describe("Agora - Agent Task Marketplace", () => {
       expect(Number(freelancerBalance.amount)).to.equal(4500000);
       
       // Step 8: Submit mutual reviews
-      const clientReview = Keypair.generate();
-      const freelancerReview = Keypair.generate();
+      const clientReviewPda = getReviewPda(taskKeypair.publicKey, client.publicKey);
+      const freelancerReviewPda = getReviewPda(taskKeypair.publicKey, freelancer.publicKey);
       
       await program.methods
         .submitReview(5, "Excellent work! Delivered on time with great quality.")
@@ -2385,10 +7791,10 @@ describe("Agora - Agent Task Marketplace", () => {
           bid: bidKeypair.publicKey,
           reviewee: freelancer.publicKey,
           revieweeProfile: freelancerProfilePda,
-          review: clientReview.publicKey,
+          review: clientReviewPda,
           systemProgram: SystemProgram.programId,
         })
-        .signers([client, clientReview])
+        .signers([client])
         .rpc();
       
       await program.methods
@@ -2399,10 +7805,10 @@ describe("Agora - Agent Task Marketplace", () => {
           bid: bidKeypair.publicKey,
           reviewee: client.publicKey,
           revieweeProfile: clientProfilePda,
-          review: freelancerReview.publicKey,
+          review: freelancerReviewPda,
           systemProgram: SystemProgram.programId,
         })
-        .signers([freelancer, freelancerReview])
+        .signers([freelancer])
         .rpc();
       
       // Verify profiles updated with ratings